@@ -0,0 +1,152 @@
+use crate::agari::{self, ORPHANS};
+use crate::tiles::Tile;
+
+// Ways a hand ends with no winner before the wall runs out. Only kyuushu kyuuhai is declared, with
+// Action::DeclareMulligan, while the rest follow from the table and end the hand unless someone calls ron.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AbortiveDraw {
+    // Nine distinct terminals and honors on the first uninterrupted turn
+    KyuushuKyuuhai,
+    // All four seats open by discarding the same wind
+    SuufonRenda,
+    // The fourth riichi stands
+    SuuchaRiichi,
+    // Four kans between more than one seat, ending after the next discard
+    Suukaikan,
+    // Three players call ron on the same tile
+    SanchaHou,
+}
+
+pub(crate) fn is_kyuushu(hand: &[Tile]) -> bool {
+    let counts = agari::to_counts(hand);
+    ORPHANS.iter().filter(|&&kind| counts[kind] > 0).count() >= 9
+}
+
+#[cfg(test)]
+mod test {
+    use crate::abortive::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::*;
+    use crate::{Action, Hand, RuleError, Wind};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<AbortiveDraw>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<AbortiveDraw>();
+    }
+
+    #[test]
+    fn kyuushu_kyuuhai() {
+        let east: &[Tile] = &[PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, PinTwo, PinThree, PinFour, SouFive, SouSix];
+        let init = scripted_init([east, &[], &[], &[]], &[HonorWest, HonorNorth]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().act(Action::DeclareMulligan, Option::None).unwrap();
+
+        let state = hand.state().unwrap();
+        assert!(state.is_ended());
+        assert_eq!(state.abortive_draw(), Some(AbortiveDraw::KyuushuKyuuhai));
+        assert_eq!(hand.draw(HonorNorth).err(), Some(RuleError::HandEnded));
+        assert_eq!(hand.act(Action::DeclareTsumo, Option::None).err(), Some(RuleError::HandEnded));
+
+        // Eight kinds are not enough, and nine only count on the first turn
+        let init = scripted_init([east, &[], &[], &[]], &[ManFive, HonorNorth, HonorNorth, HonorNorth, HonorWest]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManFive).unwrap();
+        assert_eq!(hand.act(Action::DeclareMulligan, Option::None).err(), Some(RuleError::NotKyuushu));
+        hand.discard(ManFive).unwrap()
+            .draw(HonorNorth).unwrap().discard(HonorNorth).unwrap()
+            .draw(HonorNorth).unwrap().discard(HonorNorth).unwrap()
+            .draw(HonorNorth).unwrap().discard(HonorNorth).unwrap()
+            .draw(HonorWest).unwrap();
+        assert!(is_kyuushu(hand.state().unwrap().hand(Wind::East)));
+        assert_eq!(hand.act(Action::DeclareMulligan, Option::None).err(), Some(RuleError::NotKyuushu));
+    }
+
+    #[test]
+    fn suufon_renda() {
+        let init = scripted_init([&[HonorNorth], &[HonorNorth], &[HonorNorth], &[]], &[ManOne, ManTwo, ManThree, HonorNorth, ManFour]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManOne).unwrap().discard(HonorNorth).unwrap()
+            .draw(ManTwo).unwrap().discard(HonorNorth).unwrap()
+            .draw(ManThree).unwrap().discard(HonorNorth).unwrap()
+            .draw(HonorNorth).unwrap();
+        assert_eq!(hand.state().unwrap().abortive_draw(), Option::None);
+        hand.discard(HonorNorth).unwrap();
+
+        let state = hand.state().unwrap();
+        assert!(state.is_ended());
+        assert_eq!(state.abortive_draw(), Some(AbortiveDraw::SuufonRenda));
+        assert_eq!(hand.draw(ManFour).err(), Some(RuleError::HandEnded));
+        assert_eq!(hand.act(Action::CallPonByLeft, Option::None).err(), Some(RuleError::HandEnded));
+    }
+
+    #[test]
+    fn suucha_riichi() {
+        // Every seat starts tenpai on a tanki wait
+        let east = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine, HonorEast, HonorEast, HonorEast, SouOne];
+        let south = [SouOne, SouTwo, SouThree, SouFour, SouFive, SouSix, SouSeven, SouEight, SouNine, HonorSouth, HonorSouth, HonorSouth, PinOne];
+        let west = [ManOne, ManTwo, ManThree, ManFour, ManFive, ManSix, ManSeven, ManEight, ManNine, HonorWest, HonorWest, HonorWest, PinTwo];
+        let north = [PinThree, PinFour, PinFive, SouThree, SouFour, SouFive, ManThree, ManFour, ManFive, HonorRedDragon, HonorRedDragon, HonorRedDragon, ManNine];
+        let init = scripted_init([&east, &south, &west, &north], &[HonorNorth, HonorNorth, HonorNorth, HonorNorth, PinNine]);
+        let mut hand = Hand::new_from_unchecked(init);
+        for _ in 0..4 {
+            hand.draw(HonorNorth).unwrap().act(Action::DeclareRiichi, Some(HonorNorth)).unwrap();
+        }
+
+        let state = hand.state().unwrap();
+        assert_eq!(state.abortive_draw(), Some(AbortiveDraw::SuuchaRiichi));
+        assert_eq!(hand.draw(PinNine).err(), Some(RuleError::HandEnded));
+    }
+
+    #[test]
+    fn suukaikan() {
+        let east = [PinOne, PinOne, PinOne, PinOne, PinTwo, PinTwo, PinTwo, PinTwo, PinThree, PinThree, PinThree, PinThree, ManOne];
+        let south = [SouOne, SouOne, SouOne, SouOne, SouTwo, SouTwo, SouTwo, SouTwo, ManTwo, ManThree, ManFour, ManFive, ManSix];
+        let init = scripted_init([&east, &south, &[], &[]], &[HonorWest, HonorWest, ManNine]);
+        let rinshan = init.dead_wall[..4].to_vec();
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(PinOne)).unwrap().draw(rinshan[0]).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(PinTwo)).unwrap().draw(rinshan[1]).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(PinThree)).unwrap().draw(rinshan[2]).unwrap()
+            .discard(rinshan[2]).unwrap()
+            .draw(HonorWest).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(SouOne)).unwrap().draw(rinshan[3]).unwrap();
+
+        // A fifth kan is never allowed
+        assert_eq!(hand.act(Action::CallChiiOrDeclareKan, Some(SouTwo)).err(), Some(RuleError::InvalidMeld(Action::CallChiiOrDeclareKan)));
+        assert_eq!(hand.state().unwrap().abortive_draw(), Option::None);
+        hand.discard(rinshan[3]).unwrap();
+
+        let state = hand.state().unwrap();
+        assert_eq!(state.abortive_draw(), Some(AbortiveDraw::Suukaikan));
+        assert_eq!(hand.draw(ManNine).err(), Some(RuleError::HandEnded));
+    }
+
+    #[test]
+    fn sancha_hou() {
+        let south = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine, HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon, ManNine];
+        let west = [SouOne, SouTwo, SouThree, SouFour, SouFive, SouSix, SouSeven, SouEight, SouNine, HonorGreenDragon, HonorGreenDragon, HonorGreenDragon, ManNine];
+        let north = [PinTwo, PinThree, PinFour, SouTwo, SouThree, SouFour, ManTwo, ManThree, ManFour, HonorRedDragon, HonorRedDragon, HonorRedDragon, ManNine];
+        let init = scripted_init([&[], &south, &west, &north], &[ManNine]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManNine).unwrap().discard(ManNine).unwrap()
+            .act(Action::CallRonByRight, Option::None).unwrap()
+            .act(Action::CallRonByOpposite, Option::None).unwrap();
+        assert_eq!(hand.state().unwrap().wins().len(), 2);
+        hand.act(Action::CallRonByLeft, Option::None).unwrap();
+
+        let state = hand.state().unwrap();
+        assert!(state.is_ended());
+        assert_eq!(state.abortive_draw(), Some(AbortiveDraw::SanchaHou));
+        assert!(state.wins().is_empty());
+        assert_eq!(state.score_deltas(), Option::None);
+        assert_eq!(hand.events().last(), Some(Ok(crate::Event::AbortiveDraw { seat: Wind::North })));
+    }
+}
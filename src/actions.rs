@@ -1,6 +1,7 @@
 use serde_repr::{Serialize_repr, Deserialize_repr};
 use num_enum::TryFromPrimitive;
 
+// Seats relative to the discarder, in turn order: Right plays next and Left played last
 #[derive(Debug, PartialEq, Eq, Clone, Copy, TryFromPrimitive, Hash)]
 #[repr(u8)]
 pub enum Player {
@@ -9,6 +10,14 @@ pub enum Player {
     Left = 0b10,
 }
 
+impl Player {
+    // Number of seats after the discarder in turn order
+    #[inline(always)]
+    pub fn offset(&self) -> u8 {
+        *self as u8 + 1
+    }
+}
+
 #[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq, Clone, Copy, TryFromPrimitive, Default, Hash)]
 #[repr(u8)]
 pub enum Action {
@@ -18,7 +27,7 @@ pub enum Action {
 
     // Leading 01 is Chii or Kan
     // Called Chi and Declared Kans cannot occur at the same point in the game, and so can be safely parsed.
-    // Converted Kan and Closed Kan are represented together and need to be parsed by the client
+    // Converted Kan and Closed Kan are represented together, and replaying the hand resolves them into Event::Shouminkan or Event::Ankan
     // In the cases where a meld can optionally contain a hidden red five, we need to indicate if that occured.
     // Because one cannot chii on a honor tile, it is safe to adopt the third highest bit as a flag for this case
     // A complete chii action will specify the lowest tile in the sequence called in the lowest 5 bits.
@@ -28,6 +37,9 @@ pub enum Action {
     // Leading 10 is Riichi, specifying an arbitrary tile for discard
     DeclareRiichi = 0b1000_0000,
 
+    // Below the kans, 11 is open riichi with the discard in the lowest 6 bits, where the ruleset allows it
+    DeclareOpenRiichi = 0b1100_0000,
+
     // All remaining commands have 11 high bits for clarity.
     // 1110 10 is Kan
     CallKanByRight = 0b1110_1000,
@@ -52,14 +64,15 @@ pub enum Action {
     // 1111 11 are the remaining declarations made from hand
     DeclareKita = 0b1111_1100,
     DeclareTsumo = 0b1111_1101,
+    // Kyuushu kyuuhai, the only abortive draw a player declares
     DeclareMulligan = 0b1111_1110,
 }
 
 impl Action {
-    // Chi, Closed/Converted Kan, and Riichi have high bits that are less than 3.
+    // Chi, Closed/Converted Kan, and both Riichi all sit below the first tileless action
     #[inline(always)]
     pub fn has_tile(action: u8) -> bool {
-        action >> 6 < 3
+        action < Action::CallKanByRight as u8
     }
 
     #[inline(always)]
@@ -109,6 +122,7 @@ mod test {
         check_expect_action(Action::CallChiiOrDeclareKan, false, None);
         check_expect_action(Action::CallChiiWithRedFive, false, None);
         check_expect_action(Action::DeclareRiichi, false, None);
+        check_expect_action(Action::DeclareOpenRiichi, false, None);
         check_expect_action(Action::CallKanByRight, true, Some(Player::Right));
         check_expect_action(Action::CallKanByOpposite, true, Some(Player::Opposite));
         check_expect_action(Action::CallKanByLeft, true, Some(Player::Left));
@@ -145,6 +159,9 @@ mod test {
         check_expect_tile(Action::CallChiiOrDeclareKan, true);
         check_expect_tile(Action::CallChiiWithRedFive, true);
         check_expect_tile(Action::DeclareRiichi, true);
+        check_expect_tile(Action::DeclareOpenRiichi, true);
+        // The highest tile still falls short of the kans
+        assert!(Action::has_tile(Action::DeclareOpenRiichi as u8 | 37));
         check_expect_tile(Action::CallKanByRight, false);
         check_expect_tile(Action::CallKanByOpposite, false);
         check_expect_tile(Action::CallKanByLeft, false);
@@ -0,0 +1,333 @@
+use crate::replay::{Meld, MeldKind};
+use crate::tiles::Tile;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SetKind {
+    Sequence,
+    Triplet,
+    Quad,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Set {
+    pub kind: SetKind,
+    // The lowest tile, with red fives folded into their five
+    pub tile: Tile,
+    // Claimed from another player, as opposed to formed in hand or declared as a closed kan
+    pub open: bool,
+}
+
+impl Set {
+    pub fn contains(&self, tile: Tile) -> bool {
+        match (self.kind, self.tile.kind(), tile.kind()) {
+            (SetKind::Sequence, Some(lowest), Some(kind)) => (lowest..lowest + 3).contains(&kind),
+            (_, Some(own), Some(kind)) => own == kind,
+            _ => false,
+        }
+    }
+}
+
+// The shape the winning tile completed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Wait {
+    // Either end of two in a row, but not 1-2 or 8-9
+    Ryanmen,
+    // The middle of a sequence
+    Kanchan,
+    // The 3 of 1-2 or the 7 of 8-9
+    Penchan,
+    // One of two pairs
+    Shanpon,
+    // The pair
+    Tanki,
+}
+
+// One way of reading a complete hand, with every tile given as its plain kind
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Decomposition {
+    // Declared melds come first in the sets, in the order they were made
+    Standard { pair: Tile, sets: [Set; 4] },
+    Chiitoitsu { pairs: [Tile; 7] },
+    Kokushi { pair: Tile },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Agari {
+    // Empty when the hand is not complete
+    pub decompositions: Vec<Decomposition>,
+    // Every red five in the hand, concealed or melded
+    pub red_fives: Vec<Tile>,
+}
+
+impl Agari {
+    pub fn is_complete(&self) -> bool {
+        !self.decompositions.is_empty()
+    }
+}
+
+// Enumerates every reading of the concealed tiles, winning tile included, alongside the declared melds
+pub fn decompose(concealed: &[Tile], melds: &[Meld]) -> Agari {
+    let red_fives = concealed.iter()
+        .chain(melds.iter().flat_map(|meld| meld.tiles.iter()))
+        .filter(|tile| tile.is_red_five())
+        .copied()
+        .collect();
+
+    let fixed: Vec<Set> = melds.iter().filter_map(meld_to_set).collect();
+    Agari { decompositions: decompose_counts(&to_counts(concealed), &fixed), red_fives }
+}
+
+fn meld_to_set(meld: &Meld) -> Option<Set> {
+    let tile = meld.tiles.iter().map(Tile::normalized).min_by_key(|tile| tile.kind())?;
+    let (kind, open) = match meld.kind {
+        MeldKind::Chii => (SetKind::Sequence, true),
+        MeldKind::Pon => (SetKind::Triplet, true),
+        MeldKind::CalledKan | MeldKind::AddedKan => (SetKind::Quad, true),
+        MeldKind::ClosedKan => (SetKind::Quad, false),
+    };
+    Some(Set { kind, tile, open })
+}
+
+fn decompose_counts(counts: &[u8; 34], fixed: &[Set]) -> Vec<Decomposition> {
+    let total: usize = counts.iter().map(|&count| count as usize).sum();
+    if total + 3 * fixed.len() != 14 { return vec![] }
+
+    let mut decompositions = vec![];
+    if fixed.is_empty() {
+        if is_seven_pairs(counts) {
+            let pairs: Vec<Tile> = (0..34).filter(|&kind| counts[kind] == 2).map(Tile::from_kind).collect();
+            decompositions.push(Decomposition::Chiitoitsu { pairs: pairs.try_into().unwrap() });
+        }
+        if let Some(pair) = ORPHANS.into_iter().find(|&kind| counts[kind] == 2).filter(|_| is_thirteen_orphans(counts)) {
+            decompositions.push(Decomposition::Kokushi { pair: Tile::from_kind(pair) });
+        }
+    }
+
+    let mut counts = *counts;
+    for pair in 0..34 {
+        if counts[pair] < 2 { continue }
+        counts[pair] -= 2;
+        let mut found = vec![];
+        find_sets(&mut counts, 0, &mut fixed.to_vec(), &mut found);
+        counts[pair] += 2;
+
+        decompositions.extend(found.into_iter().map(|sets| Decomposition::Standard {
+            pair: Tile::from_kind(pair),
+            sets: sets.try_into().unwrap(),
+        }));
+    }
+
+    decompositions
+}
+
+// Every way the winning tile can have completed a decomposition, as its wait and the index of the
+// set it finished, or None for the pair. Only sets past the fixed melds count, as melds are never won into.
+pub(crate) fn placements(decomposition: &Decomposition, tile: Tile, fixed: usize) -> Vec<(Wait, Option<usize>)> {
+    let Some(kind) = tile.kind() else { return vec![] };
+    match decomposition {
+        Decomposition::Chiitoitsu { .. } | Decomposition::Kokushi { .. } => vec![(Wait::Tanki, None)],
+        Decomposition::Standard { pair, sets } => {
+            let mut found = vec![];
+            if pair.kind() == Some(kind) {
+                found.push((Wait::Tanki, None));
+            }
+            for (index, set) in sets.iter().enumerate().skip(fixed) {
+                if !set.contains(tile) { continue }
+                let wait = match set.kind {
+                    SetKind::Triplet | SetKind::Quad => Wait::Shanpon,
+                    SetKind::Sequence => {
+                        let lowest = set.tile.number().unwrap();
+                        let won = tile.number().unwrap();
+                        match won - lowest {
+                            1 => Wait::Kanchan,
+                            0 if lowest == 7 => Wait::Penchan,
+                            2 if lowest == 1 => Wait::Penchan,
+                            _ => Wait::Ryanmen,
+                        }
+                    },
+                };
+                // Identical sets give the same reading, so only the first is kept
+                let repeated = found.iter().any(|&(other, at)| other == wait && at.is_some_and(|at| sets[at] == *set));
+                if !repeated {
+                    found.push((wait, Some(index)));
+                }
+            }
+            found
+        },
+    }
+}
+
+// Folds a set of tiles into per-kind counts, ignoring anything that isn't a real tile
+pub(crate) fn to_counts(tiles: &[Tile]) -> [u8; 34] {
+    let mut counts = [0u8; 34];
+    for kind in tiles.iter().filter_map(|tile| tile.kind()) {
+        counts[kind] += 1;
+    }
+    counts
+}
+
+// A concealed set of 3n+2 tiles is complete when it forms the remaining sets plus a pair,
+// or, for a fully concealed hand, seven pairs or thirteen orphans.
+pub(crate) fn is_complete(concealed: &[Tile], meld_count: usize) -> bool {
+    if concealed.len() + 3 * meld_count != 14 { return false }
+
+    // Only the number of melds matters here, so stand in a triplet for each
+    let fixed = vec![Set { kind: SetKind::Triplet, tile: Tile::None, open: true }; meld_count];
+    !decompose_counts(&to_counts(concealed), &fixed).is_empty()
+}
+
+// Removes sets starting from the lowest remaining kind, recording each complete split. Triplets
+// are tried before sequences, so the same sets are never found twice.
+fn find_sets(counts: &mut [u8; 34], from: usize, sets: &mut Vec<Set>, found: &mut Vec<Vec<Set>>) {
+    let Some(kind) = (from..34).find(|&kind| counts[kind] > 0) else {
+        found.push(sets.clone());
+        return
+    };
+
+    if counts[kind] >= 3 {
+        counts[kind] -= 3;
+        sets.push(Set { kind: SetKind::Triplet, tile: Tile::from_kind(kind), open: false });
+        find_sets(counts, kind, sets, found);
+        sets.pop();
+        counts[kind] += 3;
+    }
+
+    if is_sequence_start(kind) && counts[kind + 1] > 0 && counts[kind + 2] > 0 {
+        counts[kind] -= 1;
+        counts[kind + 1] -= 1;
+        counts[kind + 2] -= 1;
+        sets.push(Set { kind: SetKind::Sequence, tile: Tile::from_kind(kind), open: false });
+        find_sets(counts, kind, sets, found);
+        sets.pop();
+        counts[kind] += 1;
+        counts[kind + 1] += 1;
+        counts[kind + 2] += 1;
+    }
+}
+
+#[inline(always)]
+pub(crate) fn is_sequence_start(kind: usize) -> bool {
+    kind < 27 && kind % 9 < 7
+}
+
+pub(crate) fn is_seven_pairs(counts: &[u8; 34]) -> bool {
+    counts.iter().all(|&count| count == 0 || count == 2) && counts.iter().filter(|&&count| count == 2).count() == 7
+}
+
+pub(crate) const ORPHANS: [usize; 13] = [0, 8, 9, 17, 18, 26, 27, 28, 29, 30, 31, 32, 33];
+
+// Every orphan with one of them paired, and nothing else
+pub(crate) fn is_thirteen_orphans(counts: &[u8; 34]) -> bool {
+    let orphans: u8 = ORPHANS.iter().map(|&kind| counts[kind]).sum();
+    let total: u8 = counts.iter().sum();
+    total == 14 && orphans == 14 && ORPHANS.iter().all(|&kind| counts[kind] > 0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::agari::*;
+    use crate::replay::Meld;
+    use crate::tiles::Tile::*;
+    use crate::Wind;
+
+    #[test]
+    fn validate_complete() {
+        assert!(is_complete(&[
+            PinOne, PinTwo, PinThree, SouFour, SouRedFive, SouSix, ManSeven, ManEight, ManNine,
+            HonorEast, HonorEast, HonorEast, HonorRedDragon, HonorRedDragon,
+        ], 0));
+        assert!(is_complete(&[
+            PinOne, PinOne, PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight,
+            PinNine, PinNine, PinNine, PinFive,
+        ], 0));
+        assert!(is_complete(&[SouOne, SouOne, SouTwo, SouTwo, SouThree, SouThree, ManOne, ManOne], 2));
+        assert!(!is_complete(&[SouOne, SouOne, SouTwo, SouTwo, SouThree, SouThree, ManOne, ManTwo], 2));
+        assert!(!is_complete(&[SouOne, SouOne, SouTwo], 0));
+    }
+
+    #[test]
+    fn validate_special() {
+        assert!(is_complete(&[
+            PinOne, PinOne, SouTwo, SouTwo, ManThree, ManThree, ManFive, ManRedFive,
+            HonorEast, HonorEast, HonorNorth, HonorNorth, HonorWhiteDragon, HonorWhiteDragon,
+        ], 0));
+        assert!(is_complete(&[
+            PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest,
+            HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorGreenDragon, HonorGreenDragon,
+        ], 0));
+        // Four of a kind is not two pairs
+        assert!(!is_complete(&[
+            PinOne, PinOne, PinOne, PinOne, ManThree, ManThree, ManFive, ManFive,
+            HonorEast, HonorEast, HonorNorth, HonorNorth, HonorWhiteDragon, HonorWhiteDragon,
+        ], 0));
+    }
+
+    #[test]
+    fn enumerate_decompositions() {
+        // Three identical sequences or three triplets
+        let agari = decompose(&[
+            PinOne, PinOne, PinOne, PinTwo, PinTwo, PinTwo, PinThree, PinThree, PinThree,
+            SouSeven, SouEight, SouNine, ManRedFive, ManFive,
+        ], &[]);
+        assert_eq!(agari.red_fives, vec![ManRedFive]);
+        assert_eq!(agari.decompositions.len(), 2);
+        let triplet = |tile| Set { kind: SetKind::Triplet, tile, open: false };
+        let sequence = |tile| Set { kind: SetKind::Sequence, tile, open: false };
+        assert!(agari.decompositions.contains(&Decomposition::Standard {
+            pair: ManFive,
+            sets: [triplet(PinOne), triplet(PinTwo), triplet(PinThree), sequence(SouSeven)],
+        }));
+        assert!(agari.decompositions.contains(&Decomposition::Standard {
+            pair: ManFive,
+            sets: [sequence(PinOne), sequence(PinOne), sequence(PinOne), sequence(SouSeven)],
+        }));
+        assert!(sequence(PinOne).contains(PinThree));
+        assert!(!sequence(PinOne).contains(PinFour));
+    }
+
+    #[test]
+    fn enumerate_pairs_and_orphans() {
+        // Two identical sequences twice over, or seven pairs
+        let agari = decompose(&[
+            PinOne, PinOne, PinTwo, PinTwo, PinThree, PinThree, SouFour, SouFour, SouFive, SouFive,
+            SouSix, SouSix, HonorNorth, HonorNorth,
+        ], &[]);
+        assert_eq!(agari.decompositions.len(), 2);
+        assert!(matches!(agari.decompositions[0], Decomposition::Chiitoitsu { .. }));
+
+        let agari = decompose(&[
+            PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest,
+            HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorGreenDragon, PinNine,
+        ], &[]);
+        assert_eq!(agari.decompositions, vec![Decomposition::Kokushi { pair: PinNine }]);
+
+        // Thirteen orphans and a stray tile are not kokushi
+        let stray = [
+            PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest,
+            HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorGreenDragon, ManTwo,
+        ];
+        assert!(!decompose(&stray, &[]).is_complete());
+        assert!(!is_complete(&stray, 0));
+
+        assert!(!decompose(&[PinOne, PinTwo], &[]).is_complete());
+    }
+
+    #[test]
+    fn include_melds() {
+        let melds = [
+            Meld { kind: MeldKind::Chii, tiles: vec![SouFour, SouSix, SouRedFive], called: Some(SouRedFive), from: Some(Wind::North) },
+            Meld { kind: MeldKind::ClosedKan, tiles: vec![HonorWest; 4], called: Option::None, from: Option::None },
+        ];
+        let agari = decompose(&[ManOne, ManTwo, ManThree, PinNine, PinNine, PinNine, ManFive, ManFive], &melds);
+        assert_eq!(agari.red_fives, vec![SouRedFive]);
+        assert_eq!(agari.decompositions, vec![Decomposition::Standard {
+            pair: ManFive,
+            sets: [
+                Set { kind: SetKind::Sequence, tile: SouFour, open: true },
+                Set { kind: SetKind::Quad, tile: HonorWest, open: false },
+                Set { kind: SetKind::Triplet, tile: PinNine, open: false },
+                Set { kind: SetKind::Sequence, tile: ManOne, open: false },
+            ],
+        }]);
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use crate::mode::Mode;
+use crate::tiles::Tile;
+
+// The dead wall as stored in InitialState, along with how many of its dora indicators are face up.
+// Each ura indicator sits beneath the dora indicator at the same offset and is only turned for riichi wins.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct DeadWall {
+    tiles: Vec<Tile>,
+    revealed: usize,
+    mode: Mode,
+}
+
+impl DeadWall {
+    pub const RINSHAN: Range<usize> = 0..4;
+    pub const DORA: Range<usize> = 4..9;
+    pub const URA: Range<usize> = 9..14;
+
+    // Only the first dora indicator starts face up
+    pub fn new(tiles: &[Tile]) -> Self {
+        Self::new_for(tiles, Mode::Yonma)
+    }
+
+    // Sanma indicators skip the missing man tiles
+    pub fn new_for(tiles: &[Tile], mode: Mode) -> Self {
+        Self { tiles: tiles.to_vec(), revealed: 1, mode }
+    }
+
+    // The first four replacement draws after a kan, in draw order
+    pub fn rinshan_tiles(&self) -> &[Tile] {
+        self.slice(Self::RINSHAN)
+    }
+
+    pub fn revealed(&self) -> usize {
+        self.revealed
+    }
+
+    pub fn dora_indicators(&self) -> &[Tile] {
+        self.slice(Self::DORA.start..Self::DORA.start + self.revealed)
+    }
+
+    pub fn ura_indicators(&self) -> &[Tile] {
+        self.slice(Self::URA.start..Self::URA.start + self.revealed)
+    }
+
+    pub fn dora(&self) -> Vec<Tile> {
+        self.dora_indicators().iter().map(|indicator| self.mode.dora_from_indicator(indicator)).collect()
+    }
+
+    pub fn ura_dora(&self) -> Vec<Tile> {
+        self.ura_indicators().iter().map(|indicator| self.mode.dora_from_indicator(indicator)).collect()
+    }
+
+    // Every indicator counts on its own, so a tile pointed to twice is worth two
+    pub fn count_dora(&self, tiles: &[Tile]) -> u8 {
+        count(tiles, &self.dora())
+    }
+
+    pub fn count_ura_dora(&self, tiles: &[Tile]) -> u8 {
+        count(tiles, &self.ura_dora())
+    }
+
+    // Flips the next indicators for kans, up to the five the wall holds
+    pub(crate) fn reveal(&mut self, count: usize) {
+        self.revealed = (self.revealed + count).min(Self::DORA.len());
+    }
+
+    // Short walls only come from unchecked layouts, and simply run out early
+    fn slice(&self, range: Range<usize>) -> &[Tile] {
+        let end = range.end.min(self.tiles.len());
+        &self.tiles[range.start.min(end)..end]
+    }
+}
+
+fn count(tiles: &[Tile], dora: &[Tile]) -> u8 {
+    dora.iter()
+        .map(|dora| tiles.iter().filter(|tile| tile.normalized() == *dora).count() as u8)
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dead_wall::*;
+    use crate::tiles::Tile::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<DeadWall>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<DeadWall>();
+    }
+
+    #[test]
+    fn reveals_indicators() {
+        let tiles = [
+            SouOne, SouTwo, SouThree, SouFour,
+            PinNine, ManRedFive, HonorNorth, HonorGreenDragon, PinOne,
+            ManOne, ManTwo, ManThree, ManFour, ManFive,
+        ];
+        let mut wall = DeadWall::new(&tiles);
+        assert_eq!(wall.rinshan_tiles(), &tiles[..4]);
+        assert_eq!(wall.dora_indicators(), &[PinNine]);
+        assert_eq!(wall.ura_indicators(), &[ManOne]);
+        assert_eq!(wall.dora(), vec![PinOne]);
+
+        wall.reveal(2);
+        assert_eq!(wall.dora(), vec![PinOne, ManSix, HonorEast]);
+        assert_eq!(wall.ura_dora(), vec![ManTwo, ManThree, ManFour]);
+        assert_eq!(wall.count_dora(&[PinOne, PinOne, ManSix, HonorEast, SouFive]), 4);
+        assert_eq!(wall.count_ura_dora(&[ManTwo, ManRedFive]), 1);
+
+        wall.reveal(5);
+        assert_eq!(wall.revealed(), 5);
+        assert_eq!(wall.ura_indicators(), &tiles[9..]);
+
+        let sanma = DeadWall::new_for(&[SouOne, SouTwo, SouThree, SouFour, ManOne], Mode::Sanma);
+        assert_eq!(sanma.dora(), vec![ManNine]);
+
+        let short = DeadWall::new(&tiles[..6]);
+        assert_eq!(short.dora_indicators(), &[PinNine]);
+        assert_eq!(short.ura_indicators(), &[]);
+    }
+}
@@ -0,0 +1,117 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::mode::Mode;
+use crate::ruleset::Ruleset;
+use crate::tiles::Tile;
+use crate::validation::{RedFives, SetupError, DEAD_WALL_SIZE, HAND_SIZE};
+use crate::InitialState;
+
+// The tile set a deal is shuffled from
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct DealOptions {
+    pub mode: Mode,
+    pub red_fives: RedFives,
+}
+
+impl DealOptions {
+    pub fn from_ruleset(mode: Mode, ruleset: &Ruleset) -> Self {
+        Self { mode, red_fives: ruleset.red_fives }
+    }
+
+    // Every tile of a full set in ascending tile order, each as many times as RedFives::copies gives,
+    // as long as the mode allows the red fives
+    pub fn tiles(&self) -> Result<Vec<Tile>, SetupError> {
+        self.red_fives.check(self.mode)?;
+        Ok((Tile::PinOne as u8..=Tile::HonorGreenDragon as u8)
+            .filter_map(|raw| Tile::try_from(raw).ok())
+            .filter(|tile| self.mode.has_tile(*tile))
+            .flat_map(|tile| std::iter::repeat_n(tile, self.red_fives.copies(tile) as usize))
+            .collect())
+    }
+}
+
+impl InitialState {
+    // Shuffles a full set and deals it out front to back: 13 tiles to each seat in play starting from
+    // East, then the 14 of the dead wall, leaving the rest as the living wall in draw order.
+    // Only the tiles and mode are filled in, so a Match can prepare the rest.
+    pub fn deal<R: Rng + ?Sized>(rng: &mut R, options: &DealOptions) -> Result<Self, SetupError> {
+        let mut tiles = options.tiles()?;
+        tiles.shuffle(rng);
+        let mut init = InitialState { mode: options.mode, ..InitialState::default() };
+        let mut rest = tiles.into_iter();
+        let hands = [&mut init.east_hand, &mut init.south_hand, &mut init.west_hand, &mut init.north_hand];
+        for hand in hands.into_iter().take(options.mode.seats().len()) {
+            *hand = rest.by_ref().take(HAND_SIZE).collect();
+        }
+        init.dead_wall = rest.by_ref().take(DEAD_WALL_SIZE).collect();
+        init.living_wall = rest.collect();
+        Ok(init)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deal::*;
+    use crate::{Hand, Length, Match, Wind};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<DealOptions>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<DealOptions>();
+    }
+
+    #[test]
+    fn deals_full_sets() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(11);
+        let options = [
+            DealOptions::default(),
+            DealOptions { red_fives: RedFives::NONE, ..DealOptions::default() },
+            DealOptions { red_fives: RedFives { pin: 2, sou: 4, man: 0 }, ..DealOptions::default() },
+            DealOptions { mode: Mode::Sanma, red_fives: RedFives { pin: 1, sou: 1, man: 0 } },
+        ];
+        for options in options {
+            assert_eq!(options.tiles().map(|tiles| tiles.len()), Ok(options.mode.tile_count()));
+            for _ in 0..20 {
+                let init = InitialState::deal(&mut rng, &options).unwrap();
+                assert_eq!(init.validate(options.red_fives), Ok(()));
+            }
+        }
+
+        let sanma = DealOptions { mode: Mode::Sanma, ..DealOptions::default() };
+        assert_eq!(InitialState::deal(&mut rng, &sanma), Err(SetupError::InvalidRedFives));
+        let five = DealOptions { red_fives: RedFives { pin: 5, sou: 0, man: 0 }, ..DealOptions::default() };
+        assert_eq!(InitialState::deal(&mut rng, &five), Err(SetupError::InvalidRedFives));
+        assert_eq!(five.tiles(), Err(SetupError::InvalidRedFives));
+    }
+
+    #[test]
+    fn fill_order() {
+        let options = DealOptions::from_ruleset(Mode::Yonma, &Ruleset::default());
+        let init = InitialState::deal(&mut Xoshiro256StarStar::seed_from_u64(3), &options).unwrap();
+        assert_eq!(init, InitialState::deal(&mut Xoshiro256StarStar::seed_from_u64(3), &options).unwrap());
+
+        let mut tiles = options.tiles().unwrap();
+        tiles.shuffle(&mut Xoshiro256StarStar::seed_from_u64(3));
+        let dealt: Vec<Tile> = init.hands().into_iter().flatten().chain(&init.dead_wall).chain(&init.living_wall).copied().collect();
+        assert_eq!(dealt, tiles);
+        assert_eq!(init.east_hand[..], tiles[..13]);
+        assert_eq!(init.living_wall[0], tiles[66]);
+
+        // Ready to play once a match fills in the setup
+        let game = Match::new(Mode::Yonma, Length::Hanchan, &[25000; 4]).unwrap();
+        let mut init = init;
+        game.prepare(&mut init).unwrap();
+        let mut hand = Hand::new_from_unchecked(init.clone());
+        hand.draw(init.living_wall[0]).unwrap();
+        assert_eq!(init.prevailing_wind, Wind::East);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::tiles::Tile;
+use crate::Wind;
+
+// A stored byte resolved against the table it was played on, with every seat made absolute.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Event {
+    // Rinshan draws come from the dead wall after a kan or kita
+    Draw { seat: Wind, tile: Tile, rinshan: bool },
+    Discard { seat: Wind, tile: Tile },
+    // The sequence in order, with the claimed tile among them
+    Chii { seat: Wind, tiles: [Tile; 3], from: Wind },
+    // Red marks a red five taken from the caller's own hand
+    Pon { seat: Wind, tile: Tile, from: Wind, red: bool },
+    Daiminkan { seat: Wind, tile: Tile, from: Wind },
+    Ankan { seat: Wind, tile: Tile },
+    Shouminkan { seat: Wind, tile: Tile },
+    // Open riichi shows the hand to the table
+    Riichi { seat: Wind, discard: Tile, open: bool },
+    // From is the discarder, or the kan declarer when robbing a kan
+    Ron { seat: Wind, tile: Tile, from: Wind },
+    Tsumo { seat: Wind },
+    Kita { seat: Wind },
+    AbortiveDraw { seat: Wind },
+}
+
+impl Event {
+    // The seat that made the move
+    pub fn seat(&self) -> Wind {
+        match *self {
+            Event::Draw { seat, .. } |
+            Event::Discard { seat, .. } |
+            Event::Chii { seat, .. } |
+            Event::Pon { seat, .. } |
+            Event::Daiminkan { seat, .. } |
+            Event::Ankan { seat, .. } |
+            Event::Shouminkan { seat, .. } |
+            Event::Riichi { seat, .. } |
+            Event::Ron { seat, .. } |
+            Event::Tsumo { seat } |
+            Event::Kita { seat } |
+            Event::AbortiveDraw { seat } => seat,
+        }
+    }
+
+    pub fn is_call(&self) -> bool {
+        matches!(self,
+            Event::Chii { .. } |
+            Event::Pon { .. } |
+            Event::Daiminkan { .. } |
+            Event::Ron { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::events::*;
+    use crate::test::scripted_init;
+    use crate::{Action, Hand, RuleError};
+    use crate::tiles::Tile;
+    use crate::tiles::Tile::{PinThree, PinFour, PinFive, ManOne, ManTwo, ManThree, ManFour, ManSix, HonorEast, HonorWhiteDragon};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Event>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Event>();
+    }
+
+    #[test]
+    fn shared_encoding() {
+        // Chii, closed kan and added kan are all stored as CallChiiOrDeclareKan
+        let east: &[Tile] = &[PinThree, HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon];
+        let south: &[Tile] = &[PinFour, PinFive];
+        let west: &[Tile] = &[HonorEast, HonorEast];
+        let north: &[Tile] = &[HonorEast];
+        let wall: &[Tile] = &[HonorWhiteDragon, ManOne, ManTwo, ManThree, ManFour, ManSix, HonorEast];
+        let init = scripted_init([east, south, west, north], wall);
+        let rinshan = [init.dead_wall[0], init.dead_wall[1]];
+        let (south_discard, west_discard) = (init.south_hand[2], init.west_hand[2]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(HonorWhiteDragon).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(HonorWhiteDragon)).unwrap()
+            .draw(rinshan[0]).unwrap().discard(PinThree).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(PinThree)).unwrap()
+            .discard(south_discard).unwrap()
+            .draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(ManTwo).unwrap().discard(HonorEast).unwrap()
+            .act(Action::CallPonByLeft, None).unwrap()
+            .discard(west_discard).unwrap()
+            .draw(ManThree).unwrap().discard(ManThree).unwrap()
+            .draw(ManFour).unwrap().discard(ManFour).unwrap()
+            .draw(ManSix).unwrap().discard(ManSix).unwrap()
+            .draw(HonorEast).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(HonorEast)).unwrap()
+            .draw(rinshan[1]).unwrap();
+
+        let events: Vec<Event> = hand.events().map(Result::unwrap).collect();
+        assert_eq!(events, vec![
+            Event::Draw { seat: Wind::East, tile: HonorWhiteDragon, rinshan: false },
+            Event::Ankan { seat: Wind::East, tile: HonorWhiteDragon },
+            Event::Draw { seat: Wind::East, tile: rinshan[0], rinshan: true },
+            Event::Discard { seat: Wind::East, tile: PinThree },
+            Event::Chii { seat: Wind::South, tiles: [PinThree, PinFour, PinFive], from: Wind::East },
+            Event::Discard { seat: Wind::South, tile: south_discard },
+            Event::Draw { seat: Wind::West, tile: ManOne, rinshan: false },
+            Event::Discard { seat: Wind::West, tile: ManOne },
+            Event::Draw { seat: Wind::North, tile: ManTwo, rinshan: false },
+            Event::Discard { seat: Wind::North, tile: HonorEast },
+            Event::Pon { seat: Wind::West, tile: HonorEast, from: Wind::North, red: false },
+            Event::Discard { seat: Wind::West, tile: west_discard },
+            Event::Draw { seat: Wind::North, tile: ManThree, rinshan: false },
+            Event::Discard { seat: Wind::North, tile: ManThree },
+            Event::Draw { seat: Wind::East, tile: ManFour, rinshan: false },
+            Event::Discard { seat: Wind::East, tile: ManFour },
+            Event::Draw { seat: Wind::South, tile: ManSix, rinshan: false },
+            Event::Discard { seat: Wind::South, tile: ManSix },
+            Event::Draw { seat: Wind::West, tile: HonorEast, rinshan: false },
+            Event::Shouminkan { seat: Wind::West, tile: HonorEast },
+            Event::Draw { seat: Wind::West, tile: rinshan[1], rinshan: true },
+        ]);
+    }
+
+    #[test]
+    fn events_stop_at_error() {
+        let init = scripted_init([&[], &[], &[], &[]], &[ManOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.act_unchecked(Action::DeclareTsumo, None);
+        assert_eq!(hand.events().collect::<Vec<_>>(), vec![Err(RuleError::UnexpectedAction(Action::DeclareTsumo))]);
+    }
+}
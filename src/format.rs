@@ -0,0 +1,181 @@
+use std::fmt;
+
+use crate::actions::Action;
+use crate::Hand;
+
+// The layout every Hand is written with. Bump it whenever a key or the meaning of a stored byte changes,
+// and teach migrate how to bring the previous version forward.
+//   0: records from before the version was stored
+//   1: bytes from 0b1100_0000 up to the kans hold open riichi, where version 0 left them undefined
+//   2: the actions may be stored under "b" with their draws left to be inferred from the wall
+pub const FORMAT_VERSION: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MigrateError {
+    // Written by a newer release than this one
+    Unsupported { version: u8 },
+    // A byte that was undefined in its version and would be misread in the current one
+    InvalidByte { version: u8, byte: u8 },
+}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateError::Unsupported { version } => write!(f, "format version {version} is newer than {FORMAT_VERSION}"),
+            MigrateError::InvalidByte { version, byte } => write!(f, "byte {byte:#010b} is undefined in format version {version}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+impl Hand {
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    // Upgrades a hand read from an older archive one version at a time, so it replays as it did
+    // when it was written. Current hands come back unchanged.
+    pub fn migrate(mut self) -> Result<Self, MigrateError> {
+        if self.version > FORMAT_VERSION { return Err(MigrateError::Unsupported { version: self.version }) }
+
+        while self.version < FORMAT_VERSION {
+            match self.version {
+                0 => self.check_unversioned()?,
+                // Only adds a key
+                1 => {},
+                _ => unreachable!(),
+            }
+            self.version += 1;
+        }
+        Ok(self)
+    }
+
+    // Every byte kept its meaning, but the ones open riichi took over were undefined before
+    fn check_unversioned(&self) -> Result<(), MigrateError> {
+        let taken = Action::DeclareOpenRiichi as u8..Action::CallKanByRight as u8;
+        match self.actions.iter().find(|raw| taken.contains(raw)) {
+            Some(&byte) => Err(MigrateError::InvalidByte { version: self.version, byte }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::format::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::*;
+    use crate::{InitialState, Length, Match, Mode, Ruleset, Wind};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<MigrateError>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<MigrateError>();
+    }
+
+    fn golden_hand() -> Hand {
+        let init = scripted_init([&[], &[], &[], &[]], &[ManOne, ManTwo]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(ManTwo).unwrap().discard(ManTwo).unwrap();
+        hand
+    }
+
+    // Every optional key set, including a ruleset
+    fn golden_full() -> Hand {
+        let mut init = scripted_init([&[], &[], &[], &[]], &[]);
+        init.repeat_count = 1;
+        init.hanba_count = 2;
+        init.unclaimed_riichi_count = 3;
+        init.prevailing_wind = Wind::South;
+        init.round = 1;
+        init.scores = vec![25000, -1000, 38000, 38000];
+        let rules = Ruleset { kuitan: false, open_riichi: true, ..Ruleset::default() };
+        Hand::new_from_unchecked(init).with_ruleset(rules)
+    }
+
+    fn to_hex(hand: &Hand) -> String {
+        let mut serial = vec![];
+        ciborium::into_writer(hand, &mut serial).unwrap();
+        hex::encode(serial)
+    }
+
+    fn from_hex(golden: &str) -> Hand {
+        ciborium::from_reader(&hex::decode(golden).unwrap()[..]).unwrap()
+    }
+
+    const GOLDEN_HAND: &str = concat!(
+        "a36176026169a761658d182018211822182318241825181f18201821182218231824182561738d182118221823182418",
+        "25181f182018211822182318241825181f61778d181d181e1516171818181a181b181c181d181e181f1820616e8d1818",
+        "1819181b181c181d181e15161718181819181b181c61648e111213141718181819181b181c181d181e15161761749846",
+        "151601020304050708090a01020304050708090a01020304050708090a01020304060708090a0b0c0d0e0f111213140b",
+        "0c0d0e0f111213140b0c0d0e0f111213140b0c0d0e1061700061618415151616",
+    );
+    const GOLDEN_FULL: &str = concat!(
+        "a36176026169ac61658d182018211822182318241825181f18201821182218231824182561738d182118221823182418",
+        "25181f182018211822182318241825181f61778d181d181e1516171818181a181b181c181d181e181f1820616e8d1818",
+        "1819181b181c181d181e15161718181819181b181c61648e131415161718181819181b181c181d181e15161761749846",
+        "01020304050708090a01020304050708090a01020304050708090a01020304060708090a0b0c0d0e0f111213140b0c0d",
+        "0e0f111213140b0c0d0e0f111213140b0c0d0e101112617801616802617203617001616b01616f841961a83903e71994",
+        "701994706172a2616bf4616ff5",
+    );
+    // GOLDEN_HAND as it was written in version 1
+    const GOLDEN_V1: &str = concat!(
+        "a36176016169a761658d182018211822182318241825181f18201821182218231824182561738d182118221823182418",
+        "25181f182018211822182318241825181f61778d181d181e1516171818181a181b181c181d181e181f1820616e8d1818",
+        "1819181b181c181d181e15161718181819181b181c61648e111213141718181819181b181c181d181e15161761749846",
+        "151601020304050708090a01020304050708090a01020304050708090a01020304060708090a0b0c0d0e0f111213140b",
+        "0c0d0e0f111213140b0c0d0e0f111213140b0c0d0e1061700061618415151616",
+    );
+    // And before the version key existed
+    const GOLDEN_UNVERSIONED: &str = concat!(
+        "a26169a761658d182018211822182318241825181f18201821182218231824182561738d18211822182318241825181f",
+        "182018211822182318241825181f61778d181d181e1516171818181a181b181c181d181e181f1820616e8d1818181918",
+        "1b181c181d181e15161718181819181b181c61648e111213141718181819181b181c181d181e15161761749846151601",
+        "020304050708090a01020304050708090a01020304050708090a01020304060708090a0b0c0d0e0f111213140b0c0d0e",
+        "0f111213140b0c0d0e0f111213140b0c0d0e1061700061618415151616",
+    );
+
+    #[test]
+    fn golden_bytes() {
+        assert_eq!(to_hex(&golden_hand()), GOLDEN_HAND);
+        assert_eq!(from_hex(GOLDEN_HAND), golden_hand());
+        assert_eq!(to_hex(&golden_full()), GOLDEN_FULL);
+        assert_eq!(from_hex(GOLDEN_FULL), golden_full());
+    }
+
+    #[test]
+    fn migrates_unversioned() {
+        let old = from_hex(GOLDEN_UNVERSIONED);
+        assert_eq!(old.version(), 0);
+        let migrated = old.migrate().unwrap();
+        assert_eq!(migrated.version(), FORMAT_VERSION);
+        assert_eq!(migrated, golden_hand());
+        assert_eq!(golden_hand().migrate(), Ok(golden_hand()));
+        assert_eq!(from_hex(GOLDEN_V1).migrate(), Ok(golden_hand()));
+
+        let mut corrupt = from_hex(GOLDEN_UNVERSIONED);
+        corrupt.actions.push(0b1100_0001);
+        assert_eq!(corrupt.migrate(), Err(MigrateError::InvalidByte { version: 0, byte: 0b1100_0001 }));
+
+        let mut future = golden_hand();
+        future.version = FORMAT_VERSION + 1;
+        assert_eq!(future.migrate(), Err(MigrateError::Unsupported { version: FORMAT_VERSION + 1 }));
+
+        let mut game = Match::new(Mode::Yonma, Length::Hanchan, &[25000; 4]).unwrap();
+        let mut init = InitialState::new();
+        game.prepare(&mut init).unwrap();
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.version = 0;
+        game.push(hand).unwrap();
+        let game = game.migrate().unwrap();
+        assert_eq!(game.hands()[0].version(), FORMAT_VERSION);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::replay::RiverTile;
+use crate::waits::WaitingTile;
+
+// Why a seat may not call ron at the moment. Tsumo is allowed regardless.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Furiten {
+    // One of the seat's waits is in its own river, called away or not
+    pub permanent: bool,
+    // A winning tile went by since the seat's last discard
+    pub temporary: bool,
+    // A winning tile went by after riichi, which lasts the rest of the hand
+    pub riichi: bool,
+}
+
+impl Furiten {
+    pub fn any(&self) -> bool {
+        self.permanent || self.temporary || self.riichi
+    }
+}
+
+pub(crate) fn is_permanent(river: &[RiverTile], waits: &[WaitingTile]) -> bool {
+    river.iter().any(|discard| waits.iter().any(|wait| wait.tile == discard.tile.normalized()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::furiten::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::{self, *};
+    use crate::{Action, Hand, Wind};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Furiten>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Furiten>();
+    }
+
+    // South waits on 1-4 sou with riichi as the only yaku
+    const SOUTH: &[Tile] = &[
+        PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine,
+        HonorNorth, HonorNorth, SouTwo, SouThree,
+    ];
+
+    #[test]
+    fn permanent() {
+        let init = scripted_init([&[], SOUTH, &[], &[]], &[HonorWest, SouFour, SouOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(SouFour).unwrap().act(Action::DeclareRiichi, Some(SouFour)).unwrap()
+            .draw(SouOne).unwrap().discard(SouOne).unwrap();
+
+        let state = hand.state().unwrap();
+        assert_eq!(state.furiten(Wind::South), Furiten { permanent: true, temporary: false, riichi: false });
+        // Recorded anyway, but flagged
+        hand.act(Action::CallRonByLeft, Option::None).unwrap();
+        assert!(hand.state().unwrap().wins()[0].furiten);
+    }
+
+    #[test]
+    fn temporary_and_riichi() {
+        let init = scripted_init([&[SouOne, SouFour], SOUTH, &[], &[]], &[HonorWest, HonorWest, HonorWest, HonorGreenDragon, HonorEast, HonorEast]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().discard(HonorGreenDragon).unwrap()
+            .draw(HonorEast).unwrap().discard(SouOne).unwrap();
+        assert!(!hand.state().unwrap().furiten(Wind::South).any());
+
+        // Letting the tile go by lasts until South discards again
+        hand.draw(HonorEast).unwrap();
+        assert_eq!(hand.state().unwrap().furiten(Wind::South), Furiten { permanent: false, temporary: true, riichi: false });
+        hand.discard(HonorEast).unwrap();
+        assert!(!hand.state().unwrap().furiten(Wind::South).any());
+
+        let mut riichi = Hand::new_from_unchecked(init);
+        riichi.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().act(Action::DeclareRiichi, Some(HonorWest)).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().discard(HonorGreenDragon).unwrap()
+            .draw(HonorEast).unwrap().discard(SouOne).unwrap()
+            .draw(HonorEast).unwrap().discard(HonorEast).unwrap();
+        assert_eq!(riichi.state().unwrap().furiten(Wind::South), Furiten { permanent: false, temporary: false, riichi: true });
+    }
+}
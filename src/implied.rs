@@ -0,0 +1,125 @@
+use crate::events::Event;
+use crate::replay::{GameState, RuleError};
+use crate::ruleset::Ruleset;
+use crate::{Hand, InitialState};
+
+// Stands for a draw that cannot be left out: the last action of a log, or a draw from the living wall
+// followed by a declared kan, whose byte would otherwise be read as a chii on the discard before it.
+// It is the ron byte with no player, which is never a valid action.
+pub(crate) const DRAW_MARKER: u8 = 0b1111_1011;
+
+// The hand's actions with every draw the wall determines left out. Fails on hands that do not replay.
+pub(crate) fn omit_draws(hand: &Hand) -> Result<Vec<u8>, RuleError> {
+    let mut state = GameState::new_with_ruleset(&hand.initial_state, hand.ruleset);
+    let mut stored = vec![];
+    for (index, &raw) in hand.actions.iter().enumerate() {
+        let after_discard = state.expects_call();
+        if let Event::Draw { .. } = state.step(raw)? {
+            match hand.actions.get(index + 1) {
+                None => stored.push(DRAW_MARKER),
+                Some(next) if after_discard && next >> 6 == 0b01 => stored.push(DRAW_MARKER),
+                Some(_) => {},
+            }
+            continue
+        }
+        stored.push(raw);
+    }
+    Ok(stored)
+}
+
+// Puts back every draw omit_draws left out, replaying the hand to find each one
+pub(crate) fn restore_draws(init: &InitialState, ruleset: Ruleset, stored: &[u8]) -> Result<Vec<u8>, RuleError> {
+    let mut state = GameState::new_with_ruleset(init, ruleset);
+    let mut actions = vec![];
+    for &raw in stored {
+        if raw == DRAW_MARKER || state.needs_draw_before(raw) {
+            let tile = state.next_draw().ok_or(RuleError::UnexpectedDraw)?;
+            state.step(tile as u8)?;
+            actions.push(tile as u8);
+            if raw == DRAW_MARKER { continue }
+        }
+        state.step(raw)?;
+        actions.push(raw);
+    }
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::implied::*;
+    use crate::test::{do_serialize, scripted_init};
+    use crate::tiles::Tile::*;
+    use crate::Action;
+
+    fn round_trip(hand: &Hand) -> Vec<u8> {
+        let stored = omit_draws(hand).unwrap();
+        assert_eq!(restore_draws(&hand.initial_state, hand.ruleset, &stored), Ok(hand.actions.clone()));
+        stored
+    }
+
+    #[test]
+    fn leaves_out_draws() {
+        let south = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine, HonorNorth, HonorNorth, SouTwo, SouThree];
+        let init = scripted_init([&[SouFour], &south, &[], &[]], &[HonorWest, HonorGreenDragon, PinNine, PinNine, PinEight]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().act(Action::DeclareRiichi, Some(HonorGreenDragon)).unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinEight).unwrap();
+        // Ending on a draw keeps a marker in its place
+        assert_eq!(round_trip(&hand), vec![HonorWest as u8, Action::DeclareRiichi as u8 | HonorGreenDragon as u8, PinNine as u8, PinNine as u8, DRAW_MARKER]);
+
+        hand.discard(SouFour).unwrap().act(Action::CallRonByRight, Option::None).unwrap();
+        let stored = round_trip(&hand);
+        assert_eq!(stored.len() * 2, hand.actions.len() + 1);
+
+        hand.set_implied_draws(true);
+        let mut serial = vec![];
+        ciborium::into_writer(&hand, &mut serial).unwrap();
+        let output: Hand = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!(output, hand);
+        assert_eq!(output.to_parts().1.count(), 11);
+        let mut full = hand.clone();
+        full.set_implied_draws(false);
+        assert!(serial.len() < do_serialize(&full));
+    }
+
+    #[test]
+    fn keeps_kan_after_draw() {
+        // South draws the fourth 1 pin and declares a kan, where the same byte could chii the 2 just discarded
+        let south = [PinOne, PinOne, PinOne, PinThree];
+        let init = scripted_init([&[PinTwo], &south, &[], &[]], &[HonorWest, PinOne]);
+        let rinshan = init.dead_wall[0];
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(PinTwo).unwrap()
+            .draw(PinOne).unwrap().act(Action::CallChiiOrDeclareKan, Some(PinOne)).unwrap()
+            .draw(rinshan).unwrap().discard(rinshan).unwrap();
+        let kan = Action::CallChiiOrDeclareKan as u8 | PinOne as u8;
+        assert_eq!(round_trip(&hand), vec![PinTwo as u8, DRAW_MARKER, kan, rinshan as u8]);
+
+        // The chii needs no marker
+        let mut chii = Hand::new_from_unchecked(hand.initial_state.clone());
+        chii.draw(HonorWest).unwrap().discard(PinTwo).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(PinOne)).unwrap()
+            .discard(PinOne).unwrap();
+        assert_eq!(round_trip(&chii), vec![PinTwo as u8, kan, PinOne as u8]);
+    }
+
+    #[test]
+    fn refuses_broken_logs() {
+        let init = scripted_init([&[], &[], &[], &[]], &[ManOne]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+        hand.draw_unchecked(ManTwo);
+        assert_eq!(omit_draws(&hand), Err(RuleError::WrongDraw { expected: ManOne, found: ManTwo }));
+        assert_eq!(restore_draws(&init, Ruleset::default(), &[DRAW_MARKER, DRAW_MARKER]), Err(RuleError::UnexpectedDraw));
+
+        // Stored in full instead
+        hand.set_implied_draws(true);
+        let mut serial = vec![];
+        ciborium::into_writer(&hand, &mut serial).unwrap();
+        let output: Hand = ciborium::from_reader(&serial[..]).unwrap();
+        assert!(!output.implied_draws());
+        assert_eq!(output.to_parts().1.count(), 1);
+    }
+}
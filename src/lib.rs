@@ -1,20 +1,112 @@
 use serde_repr::{Serialize_repr, Deserialize_repr};
 use serde::{Serialize, Deserialize};
+use num_enum::TryFromPrimitive;
 
 mod tiles;
 mod actions;
 mod tile_or_action;
+mod agari;
+mod replay;
+mod events;
+mod validation;
+mod shanten;
+mod yaku;
+mod scoring;
+mod dead_wall;
+mod waits;
+mod furiten;
+mod abortive;
+mod ryuukyoku;
+mod mode;
+mod matches;
+mod ruleset;
+mod format;
+mod packed;
+mod implied;
+mod rank;
+mod deal;
 
-use crate::tile_or_action::TileOrAction;
 pub use crate::tiles::Tile;
-pub use crate::actions::Action;
+pub use crate::actions::{Action, Player};
+pub use crate::tile_or_action::{DecodeError, TileOrAction};
+pub use crate::replay::{Events, GameState, Meld, MeldKind, Replay, RiverTile, RuleError};
+pub use crate::events::Event;
+pub use crate::validation::{RedFives, SetupError};
+pub use crate::agari::{decompose, Agari, Decomposition, Set, SetKind, Wait};
+pub use crate::shanten::{shanten, standard_shanten, chiitoitsu_shanten, kokushi_shanten};
+pub use crate::yaku::{Win, Yaku};
+pub use crate::scoring::{Limit, Score};
+pub use crate::dead_wall::DeadWall;
+pub use crate::waits::{waits, is_tenpai, WaitingTile};
+pub use crate::furiten::Furiten;
+pub use crate::abortive::AbortiveDraw;
+pub use crate::ryuukyoku::ExhaustiveDraw;
+pub use crate::mode::Mode;
+pub use crate::matches::{Length, Match, MatchError, Setup};
+pub use crate::ruleset::{KanDora, MultipleRon, Ruleset};
+pub use crate::format::{MigrateError, FORMAT_VERSION};
+pub use crate::packed::PackedError;
+pub use crate::rank::{WallRank, WallRankError};
+pub use crate::deal::DealOptions;
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Default, Debug, Hash)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Hash)]
+#[serde(into = "StoredHand", try_from = "StoredHand")]
 pub struct Hand {
-    #[serde(rename="i")] 
+    version: u8,
+    initial_state: InitialState,
+    // Every action, draws included, however the hand is stored
+    actions: Vec<u8>,
+    ruleset: Ruleset,
+    implied_draws: bool,
+}
+
+// The serialized layout of Hand
+#[derive(Serialize, Deserialize)]
+struct StoredHand {
+    // Always written, so a record missing it predates versioning and reads as 0
+    #[serde(default, rename="v")]
+    version: u8,
+    #[serde(rename="i")]
     initial_state: InitialState,
     #[serde(default, rename="a", skip_serializing_if = "crate::is_default")]
-    actions: Vec<u8>
+    actions: Vec<u8>,
+    #[serde(default, rename="r", skip_serializing_if = "crate::is_default")]
+    ruleset: Ruleset,
+    // The actions with their draws left to be inferred from the wall, stored in place of "a"
+    #[serde(default, rename="b", skip_serializing_if = "Option::is_none")]
+    implied: Option<Vec<u8>>,
+}
+
+// Hands that do not replay cannot have their draws inferred, so they are always stored in full
+impl From<Hand> for StoredHand {
+    fn from(hand: Hand) -> Self {
+        let implied = if hand.implied_draws { implied::omit_draws(&hand).ok() } else { None };
+        Self {
+            version: hand.version,
+            actions: if implied.is_some() { vec![] } else { hand.actions },
+            initial_state: hand.initial_state,
+            ruleset: hand.ruleset,
+            implied,
+        }
+    }
+}
+
+impl TryFrom<StoredHand> for Hand {
+    type Error = RuleError;
+
+    fn try_from(stored: StoredHand) -> Result<Self, Self::Error> {
+        let actions = match &stored.implied {
+            Some(implied) => implied::restore_draws(&stored.initial_state, stored.ruleset, implied)?,
+            None => stored.actions,
+        };
+        Ok(Self {
+            version: stored.version,
+            initial_state: stored.initial_state,
+            actions,
+            ruleset: stored.ruleset,
+            implied_draws: stored.implied.is_some(),
+        })
+    }
 }
 
 impl Hand {
@@ -23,11 +115,20 @@ impl Hand {
     }
     pub fn new_from_unchecked(init: InitialState) -> Self {
         Self {
+            version: FORMAT_VERSION,
+            ruleset: Ruleset::for_mode(init.mode),
             initial_state: init,
-            actions: Vec::default()
+            actions: Vec::default(),
+            implied_draws: false,
         }
     }
 
+    // The rules every action is checked against, which should be chosen before anything is recorded
+    pub fn with_ruleset(mut self, ruleset: Ruleset) -> Self {
+        self.ruleset = ruleset;
+        self
+    }
+
     pub fn draw_unchecked(&mut self, tile: Tile) -> &mut Self {
         self.actions.push(tile as u8);
         self
@@ -40,19 +141,148 @@ impl Hand {
 
     pub fn act_unchecked(&mut self, action: Action, tile: Option<Tile>) -> &mut Self {
         let mut stored = action as u8;
-        if tile.is_some() {
-            stored |= tile.unwrap() as u8;
+        if let Some(tile) = tile {
+            stored |= tile as u8;
         }
-        self.actions.push(stored as u8);
+        self.actions.push(stored);
         self
     }
 
+    // The checked variants replay the whole hand so far and only record the move if it is legal
+    pub fn draw(&mut self, tile: Tile) -> Result<&mut Self, RuleError> {
+        let mut state = self.state()?;
+        if state.is_ended() { return Err(RuleError::HandEnded) }
+        if !state.expects_draw() { return Err(RuleError::UnexpectedDraw) }
+        state.step(tile as u8)?;
+        Ok(self.draw_unchecked(tile))
+    }
+
+    pub fn discard(&mut self, tile: Tile) -> Result<&mut Self, RuleError> {
+        let mut state = self.state()?;
+        if state.is_ended() { return Err(RuleError::HandEnded) }
+        if !state.expects_discard() { return Err(RuleError::UnexpectedDiscard) }
+        state.step(tile as u8)?;
+        Ok(self.discard_unchecked(tile))
+    }
+
+    // Chii takes the lowest tile of the sequence, while declared kans and riichi take the tile
+    // being melded or discarded. Every other action must be given no tile.
+    pub fn act(&mut self, action: Action, tile: Option<Tile>) -> Result<&mut Self, RuleError> {
+        let needs_tile = action != Action::None && Action::has_tile(action as u8);
+        if needs_tile != tile.is_some_and(|tile| tile != Tile::None) {
+            return Err(RuleError::UnexpectedAction(action))
+        }
+
+        let mut state = self.state()?;
+        // Rons may still follow the final discard, or another ron on the same tile
+        let is_ron = matches!(action, Action::CallRonByRight | Action::CallRonByOpposite | Action::CallRonByLeft);
+        if state.is_ended() && !is_ron { return Err(RuleError::HandEnded) }
+        let is_chii = action == Action::CallChiiWithRedFive || (action == Action::CallChiiOrDeclareKan && state.expects_call());
+        // A chii only has five bits for its tile, and honors would bleed into the flags
+        if is_chii && tile.is_some_and(|tile| tile.is_honor()) {
+            return Err(RuleError::InvalidMeld(action))
+        }
+
+        let mut stored = action as u8;
+        if let Some(tile) = tile {
+            stored |= tile as u8;
+        }
+        state.step(stored)?;
+        Ok(self.act_unchecked(action, tile))
+    }
+
     pub fn initial_state(&self) -> &InitialState {
         &self.initial_state
     }
 
+    pub fn mode(&self) -> Mode {
+        self.initial_state.mode
+    }
+
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    // Whether the hand is serialized without the draws the wall already determines. The actions
+    // themselves always hold every draw.
+    pub fn implied_draws(&self) -> bool {
+        self.implied_draws
+    }
+
+    pub fn set_implied_draws(&mut self, implied: bool) -> &mut Self {
+        self.implied_draws = implied;
+        self
+    }
+
+    // Checks the layout against the ruleset's red fives
+    pub fn validate(&self) -> Result<(), SetupError> {
+        self.initial_state.validate(self.ruleset.red_fives)
+    }
+
     pub fn to_parts(self) -> (InitialState, impl Iterator<Item = TileOrAction>) {
-        (self.initial_state, self.actions.into_iter().map(|item| TileOrAction::new_unchecked(item)))
+        (self.initial_state, self.actions.into_iter().map(TileOrAction::new_unchecked))
+    }
+
+    // Like to_parts, but fails on any stored byte that is neither a tile nor an action
+    pub fn try_to_parts(self) -> Result<(InitialState, impl Iterator<Item = TileOrAction>), DecodeError> {
+        self.check_bytes()?;
+        Ok(self.to_parts())
+    }
+
+    fn check_bytes(&self) -> Result<(), DecodeError> {
+        self.actions.iter().try_for_each(|raw| TileOrAction::new(*raw).map(|_| ()))
+    }
+
+    pub fn replay(&self) -> Replay<'_> {
+        Replay::new(&self.initial_state, self.ruleset, &self.actions)
+    }
+
+    pub fn events(&self) -> Events<'_> {
+        Events::new(&self.initial_state, self.ruleset, &self.actions)
+    }
+
+    // The table after every recorded action
+    pub fn state(&self) -> Result<GameState, RuleError> {
+        let mut state = GameState::new_with_ruleset(&self.initial_state, self.ruleset);
+        for raw in &self.actions {
+            state.step(*raw)?;
+        }
+        Ok(state)
+    }
+}
+
+impl Default for Hand {
+    fn default() -> Self {
+        Hand::new_from_unchecked(InitialState::default())
+    }
+}
+
+// Deserializes exactly like Hand, but rejects records holding bytes that are neither tiles nor actions,
+// for reading stored hands that may be corrupted or hostile.
+#[derive(Deserialize, PartialEq, Eq, Clone, Debug, Hash)]
+#[serde(try_from = "Hand")]
+pub struct CheckedHand(Hand);
+
+impl CheckedHand {
+    pub fn into_inner(self) -> Hand {
+        self.0
+    }
+}
+
+impl TryFrom<Hand> for CheckedHand {
+    type Error = DecodeError;
+
+    fn try_from(hand: Hand) -> Result<Self, Self::Error> {
+        hand.check_bytes()?;
+        Ok(Self(hand))
+    }
+}
+
+impl std::ops::Deref for CheckedHand {
+    type Target = Hand;
+
+    fn deref(&self) -> &Hand {
+        &self.0
     }
 }
 
@@ -61,7 +291,7 @@ fn is_default<T>(t: &T) -> bool where T : Default+PartialEq {
     *t == T::default() 
 }
 
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Debug, Clone, Copy, Default, Hash)]
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Debug, Clone, Copy, Default, TryFromPrimitive, Hash)]
 #[repr(u8)]
 pub enum Wind {
     #[default]
@@ -71,6 +301,26 @@ pub enum Wind {
     North = 3,
 }
 
+impl Wind {
+    // Seat winds double as absolute seats, with East as the dealer
+    pub fn next(&self) -> Wind {
+        self.relative(Player::Right)
+    }
+
+    pub fn relative(&self, player: Player) -> Wind {
+        Wind::try_from((*self as u8 + player.offset()) % 4).unwrap()
+    }
+
+    #[inline(always)]
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn tile(&self) -> Tile {
+        Tile::try_from(Tile::HonorEast as u8 + *self as u8).unwrap()
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default, Hash)]
 pub struct InitialState {
     #[serde(rename="e")] 
@@ -81,6 +331,7 @@ pub struct InitialState {
     pub west_hand: Vec<Tile>,
     #[serde(rename="n")] 
     pub north_hand: Vec<Tile>,
+    // Four replacement tiles, then five dora indicators, then the five ura dora indicators beneath them, as DeadWall reads it
     #[serde(rename="d")] 
     pub dead_wall: Vec<Tile>,
     #[serde(rename="t")] 
@@ -93,6 +344,14 @@ pub struct InitialState {
     pub unclaimed_riichi_count:u8,
     #[serde(rename="p")] 
     pub prevailing_wind: Wind,
+    #[serde(default, rename="m", skip_serializing_if = "crate::is_default")]
+    pub mode: Mode,
+    // Counted from zero within the prevailing wind, so East 3 is 2, which is also how many seats the deal has moved
+    #[serde(default, rename="k", skip_serializing_if = "crate::is_default")]
+    pub round: u8,
+    // Each seat's points before the deal, East first, or empty when not recorded
+    #[serde(default, rename="o", skip_serializing_if = "crate::is_default")]
+    pub scores: Vec<i32>,
 }
 
 impl InitialState {
@@ -150,6 +409,38 @@ mod test {
         do_serialize(&Hand::new_from_unchecked(init));
     }
 
+    #[test]
+    fn round_and_scores() {
+        let mut init = empty_init();
+        init_tiles(get_tiles(), &mut init);
+        let mut serial = vec![];
+        ciborium::into_writer(&init, &mut serial).unwrap();
+        // Records written before the fields existed read back the same
+        let map: ciborium::Value = ciborium::from_reader(&serial[..]).unwrap();
+        let keys: Vec<_> = map.as_map().unwrap().iter().filter_map(|(key, _)| key.as_text()).collect();
+        assert!(!keys.contains(&"k") && !keys.contains(&"o"));
+        let output: InitialState = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!((output.round, output.scores), (0, vec![]));
+
+        init.round = 2;
+        init.scores = vec![25000, 31000, 19000, 25000];
+        init.unclaimed_riichi_count = 1;
+        let hand = Hand::new_from_unchecked(init);
+        do_serialize(&hand);
+        assert_eq!(hand.state().unwrap().final_scores(), Option::None);
+
+        let mut hand = hand;
+        let living_wall = hand.initial_state.living_wall.clone();
+        for tile in &living_wall {
+            hand.draw_unchecked(*tile).discard_unchecked(*tile);
+        }
+        let state = hand.state().unwrap();
+        let deltas = state.settlement_deltas().unwrap();
+        let scores = state.final_scores().unwrap();
+        assert_eq!(scores, [25000, 31000, 19000, 25000].iter().zip(deltas).map(|(score, delta)| score + delta).collect::<Vec<_>>());
+        assert_eq!(scores.iter().sum::<i32>(), 100000);
+    }
+
     #[test]
     fn unchecked_invalid_stable_game() {
         let mut init = empty_init();
@@ -162,7 +453,7 @@ mod test {
         init_tiles(tiles, &mut init);
 
         let mut hand = Hand::new_from_unchecked(init);
-        let living_wall = (*(&hand.initial_state.living_wall)).clone();
+        let living_wall = hand.initial_state.living_wall.clone();
 
         for tile in living_wall {
             hand.draw_unchecked(tile)
@@ -173,7 +464,144 @@ mod test {
         do_serialize(&hand);
     }
 
-    fn do_serialize(hand: &Hand) {
+    #[test]
+    fn checked_tsumogiri_game() {
+        let mut init = empty_init();
+        let mut tiles = get_tiles();
+        let mut rng =  Xoshiro256StarStar::from_seed([
+            1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0,
+            0, 0, 0,
+        ]);
+        tiles.shuffle(&mut rng);
+        init_tiles(tiles, &mut init);
+
+        let mut hand = Hand::new_from_unchecked(init);
+        let living_wall = hand.initial_state.living_wall.clone();
+        for tile in &living_wall {
+            hand.draw(*tile).unwrap().discard(*tile).unwrap();
+        }
+
+        assert_eq!(hand.draw(living_wall[0]).err(), Some(RuleError::HandEnded));
+        assert_eq!(hand.actions.len(), living_wall.len() * 2);
+        do_serialize(&hand);
+    }
+
+    #[test]
+    fn checked_rejects_out_of_turn() {
+        use tiles::Tile::*;
+        let init = scripted_init([&[], &[], &[], &[]], &[SouFour, ManNine]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        assert_eq!(hand.discard(SouFour).err(), Some(RuleError::UnexpectedDiscard));
+        assert_eq!(hand.draw(ManNine).err(), Some(RuleError::WrongDraw { expected: SouFour, found: ManNine }));
+        hand.draw(SouFour).unwrap();
+        assert_eq!(hand.draw(ManNine).err(), Some(RuleError::UnexpectedDraw));
+        assert_eq!(hand.discard(ManNine).err(), Some(RuleError::TileNotHeld(ManNine)));
+        assert_eq!(hand.act(Action::CallPonByLeft, Option::None).err(), Some(RuleError::UnexpectedAction(Action::CallPonByLeft)));
+        assert_eq!(hand.act(Action::DeclareRiichi, Option::None).err(), Some(RuleError::UnexpectedAction(Action::DeclareRiichi)));
+        assert_eq!(hand.actions.len(), 1);
+    }
+
+    #[test]
+    fn checked_tsumo() {
+        use tiles::Tile::*;
+        let east: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            SouOne, SouOne, SouTwo, SouThree,
+        ];
+        let init = scripted_init([east, &[], &[], &[]], &[ManOne, HonorWest, HonorWest, HonorWest, SouFour]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(ManOne).unwrap();
+        assert_eq!(hand.act(Action::DeclareTsumo, Option::None).err(), Some(RuleError::IncompleteHand));
+        hand.discard(ManOne).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(SouFour).unwrap()
+            .act(Action::DeclareTsumo, Option::None).unwrap();
+
+        assert_eq!(hand.draw(ManOne).err(), Some(RuleError::HandEnded));
+        assert_eq!(hand.act(Action::DeclareTsumo, Option::None).err(), Some(RuleError::HandEnded));
+        do_serialize(&hand);
+    }
+
+    #[test]
+    fn checked_calls() {
+        use tiles::Tile::*;
+        let east: &[Tile] = &[HonorEast, PinFive];
+        let south: &[Tile] = &[HonorEast, HonorEast];
+        let west: &[Tile] = &[PinRedFive, PinFive];
+        let init = scripted_init([east, south, west, &[]], &[ManOne, ManTwo, ManThree, ManFour]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(ManOne).unwrap().discard(HonorEast).unwrap();
+        // West holds a single east at most, so only South has the pair
+        assert_eq!(hand.act(Action::CallPonByOpposite, Option::None).err(), Some(RuleError::InvalidMeld(Action::CallPonByOpposite)));
+        hand.act(Action::CallPonByRight, Option::None).unwrap();
+        assert_eq!(hand.draw(ManTwo).err(), Some(RuleError::UnexpectedDraw));
+        let discard = hand.initial_state.south_hand[2];
+        hand.discard(discard).unwrap()
+            .draw(ManTwo).unwrap().discard(ManTwo).unwrap()
+            .draw(ManThree).unwrap().discard(ManThree).unwrap()
+            .draw(ManFour).unwrap().discard(PinFive).unwrap();
+
+        // West must flag the red five it uses
+        assert_eq!(hand.act(Action::CallPonByOpposite, Option::None).err(), Some(RuleError::InvalidMeld(Action::CallPonByOpposite)));
+        hand.act(Action::CallPonByOppositeWithRedFive, Option::None).unwrap();
+        do_serialize(&hand);
+
+        hand.draw_unchecked(Tile::None);
+        assert_eq!(hand.discard(PinFive).err(), Some(RuleError::InvalidByte(0)));
+    }
+
+    #[test]
+    fn checked_chii() {
+        use tiles::Tile::*;
+        let east: &[Tile] = &[PinThree];
+        let south: &[Tile] = &[PinFour, PinRedFive, PinFive];
+        let init = scripted_init([east, south, &[], &[]], &[ManOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(ManOne).unwrap().discard(PinThree).unwrap();
+        assert_eq!(hand.act(Action::CallChiiOrDeclareKan, Some(PinTwo)).err(), Some(RuleError::InvalidMeld(Action::CallChiiOrDeclareKan)));
+        assert_eq!(hand.act(Action::CallChiiOrDeclareKan, Some(HonorWest)).err(), Some(RuleError::InvalidMeld(Action::CallChiiOrDeclareKan)));
+        hand.act(Action::CallChiiWithRedFive, Some(PinThree)).unwrap();
+        assert_eq!(hand.discard(PinRedFive).err(), Some(RuleError::TileNotHeld(PinRedFive)));
+        hand.discard(PinFive).unwrap();
+        do_serialize(&hand);
+    }
+
+    #[test]
+    fn checked_decoding() {
+        let init = scripted_init([&[], &[], &[], &[]], &[]);
+        let mut hand = Hand::new_from_unchecked(init);
+        let tile = hand.initial_state.living_wall[0];
+        hand.draw(tile).unwrap().discard(tile).unwrap();
+
+        let mut serial = vec![];
+        ciborium::into_writer(&hand, &mut serial).unwrap();
+        let checked: CheckedHand = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!(*checked, hand);
+        assert_eq!(checked.into_inner().try_to_parts().unwrap().1.count(), 2);
+
+        // Values past the last tile and undefined actions must not panic
+        for raw in [38, 63, 0b1110_0110, 0b1111_1111] {
+            let mut corrupt = hand.clone();
+            corrupt.actions.push(raw);
+            let mut serial = vec![];
+            ciborium::into_writer(&corrupt, &mut serial).unwrap();
+
+            assert!(ciborium::from_reader::<CheckedHand, _>(&serial[..]).is_err());
+            assert_eq!(corrupt.clone().try_to_parts().err().map(|error| error.byte()), Some(raw));
+            assert_eq!(corrupt.state().err(), Some(RuleError::InvalidByte(raw)));
+            // Plain deserialization still accepts anything
+            assert_eq!(ciborium::from_reader::<Hand, _>(&serial[..]).unwrap(), corrupt);
+        }
+    }
+
+    // Round-trips the hand through CBOR and returns how many bytes it took
+    pub(crate) fn do_serialize(hand: &Hand) -> usize {
         let mut serial = vec![];
         ciborium::into_writer(&hand, &mut serial).ok();
 
@@ -185,9 +613,10 @@ mod test {
         println!("Byte count: {len}");
         println!("{:?}", output);
         assert_eq!(*hand, output);
+        len
     }
 
-    fn init_tiles(mut tiles: Vec<Tile>, state: &mut InitialState) {
+    pub(crate) fn init_tiles(mut tiles: Vec<Tile>, state: &mut InitialState) {
         state.east_hand = tiles.split_off(tiles.len() - 13);
         state.south_hand = tiles.split_off(tiles.len() - 13);
         state.west_hand = tiles.split_off(tiles.len() - 13);
@@ -196,7 +625,37 @@ mod test {
         state.living_wall = tiles;
     }
 
-    fn empty_init() -> InitialState {
+    // Builds a layout from a full tile set, placing the given tiles first in each hand and at
+    // the front of the living wall, then filling everything else in set order.
+    pub(crate) fn scripted_init(hands: [&[Tile]; 4], wall_front: &[Tile]) -> InitialState {
+        let mut pool = get_tiles();
+        let mut take = |wanted: &[Tile]| -> Vec<Tile> {
+            wanted.iter().map(|tile| {
+                let position = pool.iter().position(|held| held == tile).unwrap();
+                pool.remove(position)
+            }).collect()
+        };
+
+        let mut hands = hands.map(&mut take);
+        let mut living_wall = take(wall_front);
+        for hand in hands.iter_mut() {
+            let missing = 13 - hand.len();
+            hand.extend(pool.drain(pool.len() - missing..));
+        }
+
+        let mut init = empty_init();
+        let [east, south, west, north] = hands;
+        init.east_hand = east;
+        init.south_hand = south;
+        init.west_hand = west;
+        init.north_hand = north;
+        init.dead_wall = pool.split_off(pool.len() - 14);
+        living_wall.extend(pool);
+        init.living_wall = living_wall;
+        init
+    }
+
+    pub(crate) fn empty_init() -> InitialState {
         InitialState {
             repeat_count: 0,
             unclaimed_riichi_count: 0,
@@ -208,10 +667,42 @@ mod test {
             north_hand: vec![],
             dead_wall: vec![],
             living_wall: vec![],
+            mode: Mode::Yonma,
+            round: 0,
+            scores: vec![],
         }
     }
 
-    fn get_tiles() -> Vec<Tile> {
+    // Like scripted_init, for the 108 tiles of sanma with the north hand left empty
+    pub(crate) fn sanma_init(hands: [&[Tile]; 3], wall_front: &[Tile]) -> InitialState {
+        let mut pool: Vec<Tile> = get_tiles().into_iter().filter(|tile| Mode::Sanma.has_tile(*tile)).collect();
+        let mut take = |wanted: &[Tile]| -> Vec<Tile> {
+            wanted.iter().map(|tile| {
+                let position = pool.iter().position(|held| held == tile).unwrap();
+                pool.remove(position)
+            }).collect()
+        };
+
+        let mut hands = hands.map(&mut take);
+        let mut living_wall = take(wall_front);
+        for hand in hands.iter_mut() {
+            let missing = 13 - hand.len();
+            hand.extend(pool.drain(pool.len() - missing..));
+        }
+
+        let mut init = empty_init();
+        init.mode = Mode::Sanma;
+        let [east, south, west] = hands;
+        init.east_hand = east;
+        init.south_hand = south;
+        init.west_hand = west;
+        init.dead_wall = pool.split_off(pool.len() - 14);
+        living_wall.extend(pool);
+        init.living_wall = living_wall;
+        init
+    }
+
+    pub(crate) fn get_tiles() -> Vec<Tile> {
         use tiles::Tile::*;
         vec![
             PinOne,
@@ -0,0 +1,472 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+use serde_repr::{Serialize_repr, Deserialize_repr};
+use num_enum::TryFromPrimitive;
+
+use crate::mode::Mode;
+use crate::replay::RuleError;
+use crate::format::MigrateError;
+use crate::ruleset::Ruleset;
+use crate::{Hand, InitialState, Wind};
+
+#[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq, Clone, Copy, Default, TryFromPrimitive, Hash)]
+#[repr(u8)]
+pub enum Length {
+    #[default]
+    Hanchan = 0,
+    // East round only
+    Tonpuusen = 1,
+}
+
+impl Length {
+    pub fn rounds(&self) -> u8 {
+        match self {
+            Length::Hanchan => 2,
+            Length::Tonpuusen => 1,
+        }
+    }
+}
+
+// What a hand starts from, as derived from the hands before it. Players are numbered in the order they
+// first sit, so player 0 is the first dealer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Setup {
+    pub dealer: u8,
+    pub prevailing_wind: Wind,
+    pub repeat_count: u8,
+    pub hanba_count: u8,
+    pub unclaimed_riichi_count: u8,
+}
+
+impl Setup {
+    // The dealer is stored as the round, which counts the same seats
+    pub fn agrees_with(&self, init: &InitialState) -> bool {
+        init.round == self.dealer
+            && init.prevailing_wind == self.prevailing_wind
+            && init.repeat_count == self.repeat_count
+            && init.hanba_count == self.hanba_count
+            && init.unclaimed_riichi_count == self.unclaimed_riichi_count
+    }
+
+    pub fn apply(&self, init: &mut InitialState) {
+        init.round = self.dealer;
+        init.prevailing_wind = self.prevailing_wind;
+        init.repeat_count = self.repeat_count;
+        init.hanba_count = self.hanba_count;
+        init.unclaimed_riichi_count = self.unclaimed_riichi_count;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MatchError {
+    // One score per player, which is three in sanma
+    ScoreCount { expected: usize, found: usize },
+    ModeMismatch { hand: usize },
+    RulesetMismatch { hand: usize },
+    SetupMismatch { hand: usize, expected: Setup },
+    // The hand recorded starting scores other than the match's
+    ScoreMismatch { hand: usize },
+    InvalidHand { hand: usize, error: RuleError },
+    HandNotEnded { hand: usize },
+    MatchOver,
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchError::ScoreCount { expected, found } => write!(f, "the match has {found} starting scores instead of {expected}"),
+            MatchError::ModeMismatch { hand } => write!(f, "hand {hand} is not played in the match's mode"),
+            MatchError::RulesetMismatch { hand } => write!(f, "hand {hand} is not played under the match's rules"),
+            MatchError::SetupMismatch { hand, expected } => write!(f, "hand {hand} does not start from {expected:?}"),
+            MatchError::ScoreMismatch { hand } => write!(f, "hand {hand} starts from the wrong scores"),
+            MatchError::InvalidHand { hand, error } => write!(f, "hand {hand} breaks the rules: {error}"),
+            MatchError::HandNotEnded { hand } => write!(f, "hand {hand} has not ended"),
+            MatchError::MatchOver => write!(f, "the match is already over"),
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
+
+// A whole match as the ordered hands dealt in it. Only the starting scores are stored, as everything
+// else about each hand's setup follows from the hands before it.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Default, Debug, Hash)]
+pub struct Match {
+    #[serde(default, rename="m", skip_serializing_if = "crate::is_default")]
+    mode: Mode,
+    #[serde(default, rename="l", skip_serializing_if = "crate::is_default")]
+    length: Length,
+    #[serde(rename="s")]
+    starting_scores: Vec<i32>,
+    #[serde(default, rename="h", skip_serializing_if = "crate::is_default")]
+    hands: Vec<Hand>,
+    #[serde(default, rename="r", skip_serializing_if = "crate::is_default")]
+    ruleset: Ruleset,
+}
+
+// The table between two hands
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+struct Progress {
+    // How many times the deal has passed to the next player
+    rotations: u8,
+    setup: Setup,
+    scores: Vec<i32>,
+}
+
+impl Match {
+    pub fn new(mode: Mode, length: Length, starting_scores: &[i32]) -> Result<Self, MatchError> {
+        let expected = mode.seats().len();
+        if starting_scores.len() != expected {
+            return Err(MatchError::ScoreCount { expected, found: starting_scores.len() })
+        }
+        Ok(Self { mode, length, starting_scores: starting_scores.to_vec(), hands: vec![], ruleset: Ruleset::for_mode(mode) })
+    }
+
+    // Every hand must be recorded with the same ruleset, so choose it before pushing any
+    pub fn with_ruleset(mut self, ruleset: Ruleset) -> Self {
+        self.ruleset = ruleset;
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn length(&self) -> Length {
+        self.length
+    }
+
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    pub fn starting_scores(&self) -> &[i32] {
+        &self.starting_scores
+    }
+
+    pub fn hands(&self) -> &[Hand] {
+        &self.hands
+    }
+
+    // The hand still being played, which is the only one that may change
+    pub fn last_mut(&mut self) -> Option<&mut Hand> {
+        self.hands.last_mut()
+    }
+
+    // Adds the next hand once every hand before it has ended, as long as it starts where they left off
+    pub fn push(&mut self, hand: Hand) -> Result<&mut Self, MatchError> {
+        let progress = self.progress()?;
+        if self.is_over_after(&progress) { return Err(MatchError::MatchOver) }
+        self.check(self.hands.len(), &hand, &progress)?;
+        self.hands.push(hand);
+        Ok(self)
+    }
+
+    // Fills in everything the next hand's layout must agree with, starting scores included
+    pub fn prepare(&self, init: &mut InitialState) -> Result<(), MatchError> {
+        let progress = self.progress()?;
+        if self.is_over_after(&progress) { return Err(MatchError::MatchOver) }
+        progress.setup.apply(init);
+        init.mode = self.mode;
+        init.scores = self.seat_scores(&progress);
+        Ok(())
+    }
+
+    // What the next hand must start from
+    pub fn next_setup(&self) -> Result<Setup, MatchError> {
+        let progress = self.progress()?;
+        if self.is_over_after(&progress) { return Err(MatchError::MatchOver) }
+        Ok(progress.setup)
+    }
+
+    // Scores per player once every hand so far has ended
+    pub fn scores(&self) -> Result<Vec<i32>, MatchError> {
+        Ok(self.progress()?.scores)
+    }
+
+    pub fn is_over(&self) -> Result<bool, MatchError> {
+        Ok(self.is_over_after(&self.progress()?))
+    }
+
+    // The dealer of the given hand, counted from the first dealer
+    pub fn dealer(&self, hand: usize) -> Result<u8, MatchError> {
+        let mut progress = self.start()?;
+        for (index, played) in self.hands.iter().enumerate().take(hand) {
+            progress = self.settle(index, played, progress)?;
+        }
+        Ok(progress.setup.dealer)
+    }
+
+    // Replays every hand and checks that each one starts where the previous one left off, for matches
+    // that were deserialized rather than built with push
+    pub fn validate(&self) -> Result<(), MatchError> {
+        let mut progress = self.start()?;
+        for (index, hand) in self.hands.iter().enumerate() {
+            if self.is_over_after(&progress) { return Err(MatchError::MatchOver) }
+            self.check(index, hand, &progress)?;
+            if index + 1 == self.hands.len() {
+                hand.state().map_err(|error| MatchError::InvalidHand { hand: index, error })?;
+                break
+            }
+            progress = self.settle(index, hand, progress)?;
+        }
+        Ok(())
+    }
+
+    // Brings every hand up to the current format version
+    pub fn migrate(mut self) -> Result<Self, MigrateError> {
+        self.hands = self.hands.into_iter().map(Hand::migrate).collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    // Scores are only compared when the hand recorded them
+    fn check(&self, index: usize, hand: &Hand, progress: &Progress) -> Result<(), MatchError> {
+        let init = hand.initial_state();
+        if init.mode != self.mode { return Err(MatchError::ModeMismatch { hand: index }) }
+        if hand.ruleset() != self.ruleset { return Err(MatchError::RulesetMismatch { hand: index }) }
+        if !progress.setup.agrees_with(init) {
+            return Err(MatchError::SetupMismatch { hand: index, expected: progress.setup })
+        }
+        if !init.scores.is_empty() && init.scores != self.seat_scores(progress) {
+            return Err(MatchError::ScoreMismatch { hand: index })
+        }
+        Ok(())
+    }
+
+    // Player scores rearranged into seat order, East first
+    fn seat_scores(&self, progress: &Progress) -> Vec<i32> {
+        let players = self.mode.seats().len();
+        (0..players).map(|seat| progress.scores[(progress.setup.dealer as usize + seat) % players]).collect()
+    }
+
+    fn start(&self) -> Result<Progress, MatchError> {
+        let expected = self.mode.seats().len();
+        if self.starting_scores.len() != expected {
+            return Err(MatchError::ScoreCount { expected, found: self.starting_scores.len() })
+        }
+        let setup = Setup { dealer: 0, prevailing_wind: Wind::East, repeat_count: 0, hanba_count: 0, unclaimed_riichi_count: 0 };
+        Ok(Progress { rotations: 0, setup, scores: self.starting_scores.clone() })
+    }
+
+    fn progress(&self) -> Result<Progress, MatchError> {
+        let mut progress = self.start()?;
+        for (index, hand) in self.hands.iter().enumerate() {
+            progress = self.settle(index, hand, progress)?;
+        }
+        Ok(progress)
+    }
+
+    // Busting ends the match early when any player falls below zero
+    fn is_over_after(&self, progress: &Progress) -> bool {
+        let bust = self.ruleset.busting && progress.scores.iter().any(|&score| score < 0);
+        bust || progress.rotations >= self.length.rounds() * self.mode.seats().len() as u8
+    }
+
+    // Pays out one finished hand and works out who deals the next one
+    fn settle(&self, index: usize, hand: &Hand, progress: Progress) -> Result<Progress, MatchError> {
+        let state = hand.state().map_err(|error| MatchError::InvalidHand { hand: index, error })?;
+        if !state.is_ended() { return Err(MatchError::HandNotEnded { hand: index }) }
+
+        let setup = progress.setup;
+        let (deltas, dealer_keeps, hanba_count, unclaimed_riichi_count) = if let Some(deltas) = state.score_deltas() {
+            let dealer_keeps = state.wins().iter().any(|win| win.seat == Wind::East);
+            let hanba_count = if dealer_keeps { setup.hanba_count.saturating_add(1) } else { 0 };
+            (deltas, dealer_keeps, hanba_count, 0)
+        }
+        else if let Some(draw) = state.exhaustive_draw() {
+            (draw.deltas, draw.dealer_keeps, draw.hanba_count, draw.unclaimed_riichi_count)
+        }
+        else {
+            // Only riichi sticks change hands, and they stay on the table
+            let deltas = state.settlement_deltas().unwrap_or_default();
+            let declared = deltas.iter().filter(|&&delta| delta < 0).count() as u8;
+            let sticks = setup.unclaimed_riichi_count.saturating_add(declared);
+            (deltas, true, setup.hanba_count.saturating_add(1), sticks)
+        };
+
+        let players = self.mode.seats().len();
+        let mut scores = progress.scores;
+        for &seat in self.mode.seats() {
+            scores[(setup.dealer as usize + seat.index()) % players] += deltas[seat.index()];
+        }
+
+        let rotations = if dealer_keeps { progress.rotations } else { progress.rotations + 1 };
+        let setup = Setup {
+            dealer: rotations % players as u8,
+            prevailing_wind: Wind::try_from((rotations / players as u8).min(3)).unwrap(),
+            repeat_count: if dealer_keeps { setup.repeat_count.saturating_add(1) } else { 0 },
+            hanba_count,
+            unclaimed_riichi_count,
+        };
+        Ok(Progress { rotations, setup, scores })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matches::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::{self, *};
+    use crate::Action;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Match>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Match>();
+    }
+
+    // Everyone discards what they draw until the wall runs out
+    fn exhausted(setup: Setup, east: &[Tile]) -> Hand {
+        let mut init = scripted_init([east, &[], &[], &[]], &[]);
+        setup.apply(&mut init);
+        let mut hand = Hand::new_from_unchecked(init);
+        for tile in hand.initial_state().living_wall.clone() {
+            hand.draw_unchecked(tile).discard_unchecked(tile);
+        }
+        hand
+    }
+
+    const NOTEN: &[Tile] = &[
+        PinOne, PinFour, PinSeven, SouTwo, SouFive, SouEight, ManThree, ManSix, ManNine,
+        HonorEast, HonorSouth, HonorWest, HonorRedDragon,
+    ];
+    const TENPAI: &[Tile] = &[
+        PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine,
+        HonorNorth, HonorNorth, SouTwo, SouThree,
+    ];
+
+    #[test]
+    fn derives_setups() {
+        let mut game = Match::new(Mode::Yonma, Length::Tonpuusen, &[25000; 4]).unwrap();
+        let setup = game.next_setup().unwrap();
+        assert_eq!(setup, Setup { dealer: 0, prevailing_wind: Wind::East, repeat_count: 0, hanba_count: 0, unclaimed_riichi_count: 0 });
+
+        // A tenpai dealer keeps the deal through an exhaustive draw
+        game.push(exhausted(setup, TENPAI)).unwrap();
+        let setup = game.next_setup().unwrap();
+        assert_eq!(setup, Setup { dealer: 0, prevailing_wind: Wind::East, repeat_count: 1, hanba_count: 1, unclaimed_riichi_count: 0 });
+        let scores = game.scores().unwrap();
+        assert!(scores[0] > 25000);
+        assert_eq!(scores.iter().sum::<i32>(), 100000);
+
+        // A stored setup that disagrees is refused
+        let mut wrong = setup;
+        wrong.hanba_count = 0;
+        assert_eq!(game.push(exhausted(wrong, NOTEN)).err(), Some(MatchError::SetupMismatch { hand: 1, expected: setup }));
+
+        game.push(exhausted(setup, NOTEN)).unwrap();
+        let setup = game.next_setup().unwrap();
+        assert_eq!(setup.dealer, 1);
+        assert_eq!((setup.repeat_count, setup.hanba_count), (0, 2));
+        assert_eq!(game.dealer(2), Ok(1));
+
+        // East round only, so three more passes of the deal end the match
+        game.push(exhausted(setup, NOTEN)).unwrap();
+        game.push(exhausted(game.next_setup().unwrap(), NOTEN)).unwrap();
+        assert!(!game.is_over().unwrap());
+        game.push(exhausted(game.next_setup().unwrap(), NOTEN)).unwrap();
+        assert!(game.is_over().unwrap());
+        assert_eq!(game.next_setup().err(), Some(MatchError::MatchOver));
+        assert_eq!(game.validate(), Ok(()));
+
+        let mut serial = vec![];
+        ciborium::into_writer(&game, &mut serial).unwrap();
+        let output: Match = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!(output, game);
+    }
+
+    #[test]
+    fn settles_wins() {
+        let mut game = Match::new(Mode::Yonma, Length::Hanchan, &[25000; 4]).unwrap();
+        let mut init = scripted_init([&[SouFour], TENPAI, &[], &[]], &[HonorWest, HonorGreenDragon, PinNine, PinNine, PinEight]);
+        game.next_setup().unwrap().apply(&mut init);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().act(Action::DeclareRiichi, Some(HonorGreenDragon)).unwrap();
+        game.push(hand).unwrap();
+        assert_eq!(game.next_setup().err(), Some(MatchError::HandNotEnded { hand: 0 }));
+
+        game.last_mut().unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinEight).unwrap().discard(SouFour).unwrap()
+            .act(Action::CallRonByRight, Option::None).unwrap();
+
+        // The non-dealer win passes the deal on and clears the honba and sticks
+        let setup = game.next_setup().unwrap();
+        assert_eq!(setup, Setup { dealer: 1, prevailing_wind: Wind::East, repeat_count: 0, hanba_count: 0, unclaimed_riichi_count: 0 });
+        let scores = game.scores().unwrap();
+        let deltas = game.hands()[0].state().unwrap().score_deltas().unwrap();
+        assert_eq!(scores, deltas.iter().map(|delta| 25000 + delta).collect::<Vec<_>>());
+        assert!(scores[1] > 25000);
+        assert_eq!(game.validate(), Ok(()));
+    }
+
+    #[test]
+    fn records_seat_scores() {
+        let mut game = Match::new(Mode::Yonma, Length::Hanchan, &[25000; 4]).unwrap();
+        game.push(exhausted(game.next_setup().unwrap(), NOTEN)).unwrap();
+
+        let mut init = scripted_init([&[], &[], &[], &[]], &[]);
+        game.prepare(&mut init).unwrap();
+        assert_eq!(init.round, 1);
+        let players = game.scores().unwrap();
+        assert_eq!(init.scores, vec![players[1], players[2], players[3], players[0]]);
+
+        let mut wrong = init.clone();
+        wrong.scores.reverse();
+        assert_eq!(game.push(Hand::new_from_unchecked(wrong)).err(), Some(MatchError::ScoreMismatch { hand: 1 }));
+        let mut unrecorded = init.clone();
+        unrecorded.scores.clear();
+        assert!(game.clone().push(Hand::new_from_unchecked(unrecorded)).is_ok());
+        let mut round = init.clone();
+        round.round = 0;
+        assert!(matches!(game.clone().push(Hand::new_from_unchecked(round)), Err(MatchError::SetupMismatch { hand: 1, .. })));
+        game.push(Hand::new_from_unchecked(init)).unwrap();
+    }
+
+    #[test]
+    fn follows_ruleset() {
+        let busting = Ruleset { busting: true, ..Ruleset::default() };
+        let mut game = Match::new(Mode::Yonma, Length::Hanchan, &[500, 30500, 35000, 34000]).unwrap().with_ruleset(busting);
+        let setup = game.next_setup().unwrap();
+        assert_eq!(game.push(exhausted(setup, NOTEN)).err(), Some(MatchError::RulesetMismatch { hand: 0 }));
+
+        // The noten dealer drops below zero, which ends the match in the first hand
+        game.push(exhausted(setup, NOTEN).with_ruleset(busting)).unwrap();
+        assert!(game.scores().unwrap()[0] < 0);
+        assert!(game.is_over().unwrap());
+        assert_eq!(game.validate(), Ok(()));
+    }
+
+    #[test]
+    fn default_sanma_deals() {
+        use crate::DealOptions;
+        use rand::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        let mut game = Match::new(Mode::Sanma, Length::Hanchan, &[35000; 3]).unwrap();
+        assert_eq!(game.ruleset().red_fives.man, 0);
+        let options = DealOptions::from_ruleset(Mode::Sanma, &game.ruleset());
+        let mut init = InitialState::deal(&mut Xoshiro256StarStar::seed_from_u64(5), &options).unwrap();
+        game.prepare(&mut init).unwrap();
+        let hand = Hand::new_from_unchecked(init);
+        assert_eq!(hand.validate(), Ok(()));
+        game.push(hand).unwrap();
+    }
+
+    #[test]
+    fn checks_scores() {
+        assert_eq!(Match::new(Mode::Sanma, Length::Hanchan, &[25000; 4]).err(), Some(MatchError::ScoreCount { expected: 3, found: 4 }));
+        let game = Match::new(Mode::Sanma, Length::Hanchan, &[35000; 3]).unwrap();
+        assert!(game.hands().is_empty());
+        assert_eq!(game.scores(), Ok(vec![35000; 3]));
+    }
+}
@@ -0,0 +1,157 @@
+use serde_repr::{Serialize_repr, Deserialize_repr};
+use num_enum::TryFromPrimitive;
+
+use crate::actions::Player;
+use crate::tiles::Tile;
+use crate::Wind;
+
+// How many players sit at the table. Sanma drops the north seat and the 2 to 8 of man, leaving 108 tiles,
+// and every north tile may be set aside as kita for a dora and a replacement draw.
+#[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq, Clone, Copy, Default, TryFromPrimitive, Hash)]
+#[repr(u8)]
+pub enum Mode {
+    #[default]
+    Yonma = 0,
+    Sanma = 1,
+}
+
+impl Mode {
+    // The seats in play, in turn order
+    pub fn seats(&self) -> &'static [Wind] {
+        match self {
+            Mode::Yonma => &[Wind::East, Wind::South, Wind::West, Wind::North],
+            Mode::Sanma => &[Wind::East, Wind::South, Wind::West],
+        }
+    }
+
+    pub fn next(&self, seat: Wind) -> Wind {
+        match (self, seat.next()) {
+            (Mode::Sanma, Wind::North) => Wind::East,
+            (_, next) => next,
+        }
+    }
+
+    // With three players the seat to the left is two seats on, and nobody sits opposite
+    pub fn relative(&self, seat: Wind, player: Player) -> Option<Wind> {
+        match (self, player) {
+            (Mode::Yonma, _) => Some(seat.relative(player)),
+            (Mode::Sanma, Player::Right) => Some(self.next(seat)),
+            (Mode::Sanma, Player::Left) => Some(self.next(self.next(seat))),
+            (Mode::Sanma, Player::Opposite) => None,
+        }
+    }
+
+    pub fn has_tile(&self, tile: Tile) -> bool {
+        match self {
+            Mode::Yonma => true,
+            Mode::Sanma => !(Tile::ManTwo as u8..=Tile::ManEight as u8).contains(&(tile as u8)),
+        }
+    }
+
+    // Tiles in a full set, red fives included
+    pub fn tile_count(&self) -> usize {
+        match self {
+            Mode::Yonma => 136,
+            Mode::Sanma => 108,
+        }
+    }
+
+    // A 1 of man points to the 9, as nothing lies between them
+    pub fn dora_from_indicator(&self, indicator: &Tile) -> Tile {
+        match (self, indicator) {
+            (Mode::Sanma, Tile::ManOne) => Tile::ManNine,
+            _ => indicator.dora_from_indicator(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mode::*;
+    use crate::test::{sanma_init, scripted_init};
+    use crate::tiles::Tile::*;
+    use crate::validation::{RedFives, SetupError};
+    use crate::{Action, Event, Hand, RuleError};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Mode>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Mode>();
+    }
+
+    #[test]
+    fn maps_seats() {
+        assert_eq!(Mode::Sanma.next(Wind::West), Wind::East);
+        assert_eq!(Mode::Yonma.next(Wind::West), Wind::North);
+        assert_eq!(Mode::Sanma.relative(Wind::East, Player::Left), Some(Wind::West));
+        assert_eq!(Mode::Sanma.relative(Wind::South, Player::Left), Some(Wind::East));
+        assert_eq!(Mode::Sanma.relative(Wind::South, Player::Opposite), Option::None);
+        assert_eq!(Mode::Yonma.relative(Wind::South, Player::Opposite), Some(Wind::North));
+        assert!(!Mode::Sanma.has_tile(ManRedFive));
+        assert!(Mode::Sanma.has_tile(ManNine));
+        assert_eq!(Mode::Sanma.dora_from_indicator(&ManOne), ManNine);
+        assert_eq!(Mode::Sanma.dora_from_indicator(&ManNine), ManOne);
+        assert_eq!(Mode::Yonma.dora_from_indicator(&ManOne), ManTwo);
+        assert_eq!(Mode::Sanma.dora_from_indicator(&HonorWest), HonorNorth);
+    }
+
+    #[test]
+    fn validates_sanma_set() {
+        let sanma = RedFives { pin: 1, sou: 1, man: 0 };
+        let init = sanma_init([&[], &[], &[]], &[]);
+        assert_eq!(init.living_wall.len(), 55);
+        assert_eq!(init.validate(sanma), Ok(()));
+        assert_eq!(init.validate(RedFives::default()), Err(SetupError::InvalidRedFives));
+
+        let mut four = scripted_init([&[], &[], &[], &[]], &[]);
+        four.mode = Mode::Sanma;
+        assert_eq!(four.validate(sanma), Err(SetupError::HandSize { seat: Wind::North, expected: 0, found: 13 }));
+    }
+
+    #[test]
+    fn plays_three_seats() {
+        let south = [HonorEast, HonorEast];
+        let init = sanma_init([&[HonorEast], &south, &[]], &[ManNine, PinOne, SouOne, PinTwo]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManNine).unwrap().discard(ManNine).unwrap()
+            .draw(PinOne).unwrap().discard(PinOne).unwrap()
+            .draw(SouOne).unwrap().discard(SouOne).unwrap();
+        // West passes straight back to East
+        assert_eq!(hand.state().unwrap().actor(), Wind::West);
+        hand.draw(PinTwo).unwrap().discard(HonorEast).unwrap();
+
+        assert_eq!(hand.act(Action::CallChiiOrDeclareKan, Some(PinOne)).err(), Some(RuleError::UnexpectedAction(Action::CallChiiOrDeclareKan)));
+        assert_eq!(hand.act(Action::CallPonByOpposite, Option::None).err(), Some(RuleError::UnexpectedAction(Action::CallPonByOpposite)));
+        hand.act(Action::CallPonByRight, Option::None).unwrap();
+        assert_eq!(hand.events().last(), Some(Ok(Event::Pon { seat: Wind::South, tile: HonorEast, from: Wind::East, red: false })));
+    }
+
+    #[test]
+    fn declares_kita() {
+        let init = sanma_init([&[HonorNorth, HonorNorth], &[], &[]], &[PinOne]);
+        let rinshan = init.dead_wall[0];
+        let norths = |tiles: &[Tile]| tiles.iter().filter(|&&tile| tile == HonorNorth).count();
+        let before = norths(&init.east_hand);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(PinOne).unwrap().act(Action::DeclareKita, Option::None).unwrap();
+        assert_eq!(hand.events().last(), Some(Ok(Event::Kita { seat: Wind::East })));
+        assert_eq!(hand.draw(PinOne).err(), Some(RuleError::WrongDraw { expected: rinshan, found: PinOne }));
+        hand.draw(rinshan).unwrap();
+
+        let state = hand.state().unwrap();
+        assert_eq!(state.kita(Wind::East), 1);
+        assert_eq!(norths(state.hand(Wind::East)), before - 1 + norths(&[rinshan]));
+        assert_eq!(state.living_wall().len(), 53);
+
+        let four = scripted_init([&[HonorNorth], &[], &[], &[]], &[PinOne]);
+        let mut hand = Hand::new_from_unchecked(four);
+        hand.draw(PinOne).unwrap();
+        assert_eq!(hand.act(Action::DeclareKita, Option::None).err(), Some(RuleError::UnexpectedAction(Action::DeclareKita)));
+    }
+}
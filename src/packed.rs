@@ -0,0 +1,338 @@
+use std::fmt;
+
+use crate::implied::{omit_draws, restore_draws};
+use crate::mode::Mode;
+use crate::ruleset::{KanDora, MultipleRon, Ruleset};
+use crate::tiles::Tile;
+use crate::validation::RedFives;
+use crate::{Hand, InitialState, Wind};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PackedError {
+    // The input stopped in the middle of a field
+    UnexpectedEnd,
+    // A field holds a value its type does not define
+    InvalidValue,
+    // Bytes remain after the last action
+    TrailingBytes,
+}
+
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedError::UnexpectedEnd => write!(f, "the packed hand ends early"),
+            PackedError::InvalidValue => write!(f, "the packed hand holds an undefined value"),
+            PackedError::TrailingBytes => write!(f, "bytes follow the packed hand"),
+        }
+    }
+}
+
+impl std::error::Error for PackedError {}
+
+// Tiles are at most 37, and every action byte has a nonzero bit in its top two
+const TILE_BITS: u32 = 6;
+
+// A binary form of Hand that holds exactly what the serde form does, written most significant bit first:
+//   the format version in 8 bits
+//   the prevailing wind in 2 bits and the mode in 2
+//   the round, repeat count, honba and riichi sticks in 8 bits each
+//   the score count as a varint, then each score zigzagged into a varint
+//   a bit that is set when the ruleset differs from the default, then the ruleset itself
+//   the four hands, dead wall and living wall, each a varint length then 6 bits per tile
+//   a bit that is set when the actions are stored with their draws implied, as omit_draws leaves them
+//   the action count as a varint, then each action as a 0 bit and 6 for a tile, or a 1 bit and 8 for anything else
+// The last byte is padded with zeros.
+impl Hand {
+    pub fn encode_packed(&self) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        let init = &self.initial_state;
+        writer.write(self.version as u64, 8);
+        writer.write(init.prevailing_wind as u64, 2);
+        writer.write(init.mode as u64, 2);
+        for count in [init.round, init.repeat_count, init.hanba_count, init.unclaimed_riichi_count] {
+            writer.write(count as u64, 8);
+        }
+
+        writer.write_varint(init.scores.len() as u64);
+        for score in &init.scores {
+            writer.write_varint(((score << 1) ^ (score >> 31)) as u32 as u64);
+        }
+
+        writer.write_bool(self.ruleset != Ruleset::default());
+        if self.ruleset != Ruleset::default() {
+            write_ruleset(&mut writer, &self.ruleset);
+        }
+
+        let tiles = [&init.east_hand, &init.south_hand, &init.west_hand, &init.north_hand, &init.dead_wall, &init.living_wall];
+        for tiles in tiles {
+            writer.write_varint(tiles.len() as u64);
+            for tile in tiles {
+                writer.write(*tile as u64, TILE_BITS);
+            }
+        }
+
+        let implied = if self.implied_draws { omit_draws(self).ok() } else { None };
+        writer.write_bool(implied.is_some());
+        let actions = implied.as_ref().unwrap_or(&self.actions);
+        writer.write_varint(actions.len() as u64);
+        for &raw in actions {
+            let is_tile = raw >> TILE_BITS == 0;
+            writer.write_bool(!is_tile);
+            if is_tile { writer.write(raw as u64, TILE_BITS) } else { writer.write(raw as u64, 8) }
+        }
+        writer.finish()
+    }
+
+    // Reads back exactly what encode_packed wrote. Like plain deserialization, it never checks the actions,
+    // unless their draws were implied and have to be replayed to put them back.
+    pub fn decode_packed(bytes: &[u8]) -> Result<Hand, PackedError> {
+        let mut reader = BitReader::new(bytes);
+        let version = reader.read(8)? as u8;
+        let mut init = InitialState {
+            prevailing_wind: Wind::try_from(reader.read(2)? as u8).map_err(|_| PackedError::InvalidValue)?,
+            mode: Mode::try_from(reader.read(2)? as u8).map_err(|_| PackedError::InvalidValue)?,
+            ..InitialState::default()
+        };
+        init.round = reader.read(8)? as u8;
+        init.repeat_count = reader.read(8)? as u8;
+        init.hanba_count = reader.read(8)? as u8;
+        init.unclaimed_riichi_count = reader.read(8)? as u8;
+
+        let scores = reader.read_varint()?;
+        for _ in 0..scores {
+            let zigzag = u32::try_from(reader.read_varint()?).map_err(|_| PackedError::InvalidValue)?;
+            init.scores.push((zigzag >> 1) as i32 ^ -((zigzag & 1) as i32));
+        }
+
+        let ruleset = if reader.read_bool()? { read_ruleset(&mut reader)? } else { Ruleset::default() };
+
+        let tiles = [
+            &mut init.east_hand, &mut init.south_hand, &mut init.west_hand, &mut init.north_hand,
+            &mut init.dead_wall, &mut init.living_wall,
+        ];
+        for tiles in tiles {
+            let count = reader.read_varint()?;
+            for _ in 0..count {
+                tiles.push(reader.read_tile()?);
+            }
+        }
+
+        let implied_draws = reader.read_bool()?;
+        let count = reader.read_varint()?;
+        let mut actions = vec![];
+        for _ in 0..count {
+            let raw = if reader.read_bool()? { reader.read(8)? as u8 } else { reader.read(TILE_BITS)? as u8 };
+            actions.push(raw);
+        }
+        reader.finish()?;
+
+        if implied_draws {
+            actions = restore_draws(&init, ruleset, &actions).map_err(|_| PackedError::InvalidValue)?;
+        }
+        Ok(Hand { version, initial_state: init, actions, ruleset, implied_draws })
+    }
+}
+
+fn write_ruleset(writer: &mut BitWriter, ruleset: &Ruleset) {
+    let red_fives = ruleset.red_fives;
+    for count in [red_fives.pin, red_fives.sou, red_fives.man] {
+        writer.write(count as u64, 8);
+    }
+    writer.write_bool(ruleset.kuitan);
+    writer.write(ruleset.multiple_ron as u64, 2);
+    writer.write_bool(ruleset.atozuke);
+    writer.write_bool(ruleset.kiriage_mangan);
+    writer.write_bool(ruleset.open_riichi);
+    writer.write_bool(ruleset.busting);
+    writer.write(ruleset.kan_dora as u64, 2);
+}
+
+fn read_ruleset(reader: &mut BitReader) -> Result<Ruleset, PackedError> {
+    let red_fives = RedFives { pin: reader.read(8)? as u8, sou: reader.read(8)? as u8, man: reader.read(8)? as u8 };
+    Ok(Ruleset {
+        red_fives,
+        kuitan: reader.read_bool()?,
+        multiple_ron: MultipleRon::try_from(reader.read(2)? as u8).map_err(|_| PackedError::InvalidValue)?,
+        atozuke: reader.read_bool()?,
+        kiriage_mangan: reader.read_bool()?,
+        open_riichi: reader.read_bool()?,
+        busting: reader.read_bool()?,
+        kan_dora: KanDora::try_from(reader.read(2)? as u8).map_err(|_| PackedError::InvalidValue)?,
+    })
+}
+
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    // Bits already used in the last byte
+    used: u32,
+}
+
+impl BitWriter {
+    pub(crate) fn write(&mut self, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            if self.used == 0 { self.bytes.push(0) }
+            let set = (value >> bit) & 1;
+            *self.bytes.last_mut().unwrap() |= (set as u8) << (7 - self.used);
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    pub(crate) fn write_bool(&mut self, value: bool) {
+        self.write(value as u64, 1);
+    }
+
+    // Seven bits at a time, lowest first, each group led by a bit saying whether another follows
+    pub(crate) fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let more = value >= 0x80;
+            self.write_bool(more);
+            self.write(value & 0x7f, 7);
+            value >>= 7;
+            if !more { break }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct BitReader<'a> {
+    bytes: &'a [u8],
+    // Bits read so far
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub(crate) fn read(&mut self, bits: u32) -> Result<u64, PackedError> {
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self.bytes.get(self.position / 8).ok_or(PackedError::UnexpectedEnd)?;
+            value = (value << 1) | ((byte >> (7 - self.position % 8)) & 1) as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn read_bool(&mut self) -> Result<bool, PackedError> {
+        Ok(self.read(1)? == 1)
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<u64, PackedError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let more = self.read_bool()?;
+            value |= self.read(7)? << shift;
+            if !more { return Ok(value) }
+        }
+        Err(PackedError::InvalidValue)
+    }
+
+    pub(crate) fn read_tile(&mut self) -> Result<Tile, PackedError> {
+        Tile::try_from(self.read(TILE_BITS)? as u8).map_err(|_| PackedError::InvalidValue)
+    }
+
+    // Only the zero padding of the last byte may be left over
+    pub(crate) fn finish(&mut self) -> Result<(), PackedError> {
+        if self.position.div_ceil(8) != self.bytes.len() { return Err(PackedError::TrailingBytes) }
+        while !self.position.is_multiple_of(8) {
+            if self.read_bool()? { return Err(PackedError::InvalidValue) }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::packed::*;
+    use crate::test::{do_serialize, get_tiles, init_tiles, scripted_init};
+    use crate::tiles::Tile::*;
+    use crate::Action;
+    use rand::prelude::SliceRandom;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<PackedError>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<PackedError>();
+    }
+
+    fn played() -> Hand {
+        let south = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine, HonorNorth, HonorNorth, SouTwo, SouThree];
+        let mut init = scripted_init([&[SouFour], &south, &[], &[]], &[HonorWest, HonorGreenDragon, PinNine, PinNine, PinEight]);
+        init.prevailing_wind = Wind::South;
+        init.round = 3;
+        init.hanba_count = 2;
+        init.scores = vec![25000, -2300, 41000, 36300];
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().act(Action::DeclareRiichi, Some(HonorGreenDragon)).unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinNine).unwrap().discard(PinNine).unwrap()
+            .draw(PinEight).unwrap().discard(SouFour).unwrap()
+            .act(Action::CallRonByRight, Option::None).unwrap();
+        hand
+    }
+
+    #[test]
+    fn round_trips() {
+        let hand = played();
+        assert_eq!(Hand::decode_packed(&hand.encode_packed()), Ok(hand.clone()));
+        let mut implied = hand.clone();
+        implied.set_implied_draws(true);
+        assert_eq!(Hand::decode_packed(&implied.encode_packed()), Ok(implied.clone()));
+        assert!(implied.encode_packed().len() < hand.encode_packed().len());
+
+        let rules = Ruleset { red_fives: RedFives::NONE, multiple_ron: MultipleRon::Triple, kan_dora: KanDora::Delayed, ..Ruleset::default() };
+        let ruled = hand.with_ruleset(rules);
+        assert_eq!(Hand::decode_packed(&ruled.encode_packed()), Ok(ruled));
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(7);
+        for _ in 0..20 {
+            let mut tiles = get_tiles();
+            tiles.shuffle(&mut rng);
+            let mut init = InitialState::new();
+            init_tiles(tiles, &mut init);
+            let hand = Hand::new_from_unchecked(init);
+            assert_eq!(Hand::decode_packed(&hand.encode_packed()), Ok(hand));
+        }
+        assert_eq!(Hand::decode_packed(&Hand::default().encode_packed()), Ok(Hand::default()));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let packed = played().encode_packed();
+        assert_eq!(Hand::decode_packed(&packed[..packed.len() - 1]), Err(PackedError::UnexpectedEnd));
+        let mut longer = packed.clone();
+        longer.push(0);
+        assert_eq!(Hand::decode_packed(&longer), Err(PackedError::TrailingBytes));
+        // The mode bits of 3 name no mode
+        let mut mode = packed.clone();
+        mode[1] |= 0b0011_0000;
+        assert_eq!(Hand::decode_packed(&mode), Err(PackedError::InvalidValue));
+        assert_eq!(Hand::decode_packed(&[]), Err(PackedError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn smaller_than_cbor() {
+        let hand = played();
+        let packed = hand.encode_packed().len();
+        let cbor = do_serialize(&hand);
+        println!("Packed byte count: {packed}");
+        // The 136 tiles take 102 bytes, leaving the header, scores and actions well under 40
+        assert!(packed < 102 + 40);
+        assert!(packed * 5 < cbor * 3);
+    }
+}
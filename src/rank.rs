@@ -0,0 +1,322 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::tiles::Tile;
+use crate::InitialState;
+
+// Every tile value, Tile::None included
+const TILE_VALUES: usize = Tile::HonorGreenDragon as usize + 1;
+
+// An InitialState's tile layout as the tiles it holds and the rank of their order among every distinct
+// ordering of them, counting up from the order that sorts each tile before any of higher value.
+// A full wall needs about 78 bytes of rank where the six fields hold 136.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct WallRank {
+    // How many of each tile the layout holds, indexed by tile value
+    pub counts: [u8; TILE_VALUES],
+    // The lengths of the east, south, west and north hands, the dead wall and the living wall, which
+    // are ranked as one sequence in that order
+    pub lengths: [u8; 6],
+    // Big endian, without leading zeros
+    pub rank: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum WallRankError {
+    // A field, or the number of one tile, does not fit in a byte
+    TooManyTiles,
+    // The lengths do not add up to the counts
+    LengthMismatch,
+    // The rank is not below the number of orderings the counts allow
+    RankTooLarge,
+}
+
+impl fmt::Display for WallRankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WallRankError::TooManyTiles => write!(f, "the layout holds too many tiles to rank"),
+            WallRankError::LengthMismatch => write!(f, "the lengths do not add up to the tile counts"),
+            WallRankError::RankTooLarge => write!(f, "the rank is past the last ordering of the tiles"),
+        }
+    }
+}
+
+impl std::error::Error for WallRankError {}
+
+impl InitialState {
+    pub fn wall_rank(&self) -> Result<WallRank, WallRankError> {
+        let fields = self.tile_fields();
+        let mut lengths = [0; 6];
+        for (length, field) in lengths.iter_mut().zip(fields) {
+            *length = u8::try_from(field.len()).map_err(|_| WallRankError::TooManyTiles)?;
+        }
+
+        let mut counts = [0u8; TILE_VALUES];
+        for &tile in fields.iter().flat_map(|field| field.iter()) {
+            let count = &mut counts[tile as usize];
+            *count = count.checked_add(1).ok_or(WallRankError::TooManyTiles)?;
+        }
+
+        let mut remaining = counts;
+        let mut orderings = orderings(&counts);
+        let mut left = lengths.iter().map(|&length| length as u32).sum::<u32>();
+        let mut rank = Natural::default();
+        for &tile in fields.iter().flat_map(|field| field.iter()) {
+            // Every ordering that puts a lower tile here comes first
+            for &count in remaining[..tile as usize].iter().filter(|&&count| count > 0) {
+                rank.add(&orderings.scaled(count as u32, left));
+            }
+            orderings = orderings.scaled(remaining[tile as usize] as u32, left);
+            remaining[tile as usize] -= 1;
+            left -= 1;
+        }
+
+        Ok(WallRank { counts, lengths, rank: rank.to_be_bytes() })
+    }
+
+    // Replaces the hands and walls with the layout the rank stands for, leaving every other field alone
+    pub fn set_wall_rank(&mut self, wall: &WallRank) -> Result<(), WallRankError> {
+        let total = wall.lengths.iter().map(|&length| length as u32).sum::<u32>();
+        if total != wall.counts.iter().map(|&count| count as u32).sum::<u32>() {
+            return Err(WallRankError::LengthMismatch)
+        }
+        let mut orderings = orderings(&wall.counts);
+        let mut rank = Natural::from_be_bytes(&wall.rank);
+        if rank.cmp(&orderings) != Ordering::Less { return Err(WallRankError::RankTooLarge) }
+
+        let mut remaining = wall.counts;
+        let mut left = total;
+        let mut tiles = Vec::with_capacity(total as usize);
+        while left > 0 {
+            for (value, count) in remaining.iter_mut().enumerate() {
+                if *count == 0 { continue }
+                let following = orderings.scaled(*count as u32, left);
+                if rank.cmp(&following) == Ordering::Less {
+                    orderings = following;
+                    *count -= 1;
+                    // Tile values are only ever counted from tiles, so this never fails
+                    tiles.push(Tile::try_from(value as u8).unwrap_or_default());
+                    break
+                }
+                rank.sub(&following);
+            }
+            left -= 1;
+        }
+
+        let mut tiles = tiles.into_iter();
+        let fields = [
+            &mut self.east_hand, &mut self.south_hand, &mut self.west_hand, &mut self.north_hand,
+            &mut self.dead_wall, &mut self.living_wall,
+        ];
+        for (field, &length) in fields.into_iter().zip(&wall.lengths) {
+            *field = tiles.by_ref().take(length as usize).collect();
+        }
+        Ok(())
+    }
+
+    fn tile_fields(&self) -> [&Vec<Tile>; 6] {
+        [&self.east_hand, &self.south_hand, &self.west_hand, &self.north_hand, &self.dead_wall, &self.living_wall]
+    }
+}
+
+// The number of distinct orderings of the counted tiles, built up one tile at a time from the
+// binomials C(k, j) = C(k - 1, j - 1) * k / j, so every division is exact
+fn orderings(counts: &[u8; TILE_VALUES]) -> Natural {
+    let mut orderings = Natural::from_be_bytes(&[1]);
+    let mut placed = 0;
+    for &count in counts {
+        for taken in 1..=count as u32 {
+            placed += 1;
+            orderings.mul_small(placed);
+            orderings.div_small(taken);
+        }
+    }
+    orderings
+}
+
+// Just enough of an arbitrary size unsigned integer for ranking, as 32 bit limbs with the lowest first
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct Natural(Vec<u32>);
+
+impl Natural {
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut limbs: Vec<u32> = bytes.rchunks(4).map(|chunk| {
+            chunk.iter().fold(0, |limb, &byte| (limb << 8) | byte as u32)
+        }).collect();
+        trim(&mut limbs);
+        Self(limbs)
+    }
+
+    fn to_be_bytes(&self) -> Vec<u8> {
+        let bytes: Vec<u8> = self.0.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect();
+        let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len());
+        bytes[start..].to_vec()
+    }
+
+    // The orderings left once one of a tile counted `count` times out of `left` is placed
+    fn scaled(&self, count: u32, left: u32) -> Self {
+        let mut scaled = self.clone();
+        scaled.mul_small(count);
+        scaled.div_small(left);
+        scaled
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in self.0.iter_mut() {
+            let product = *limb as u64 * factor as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 { self.0.push(carry as u32) }
+        trim(&mut self.0);
+    }
+
+    // Only ever used where the division is exact, so the remainder is dropped
+    fn div_small(&mut self, divisor: u32) {
+        let mut remainder = 0u64;
+        for limb in self.0.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        trim(&mut self.0);
+    }
+
+    fn add(&mut self, other: &Self) {
+        if self.0.len() < other.0.len() { self.0.resize(other.0.len(), 0) }
+        let mut carry = 0u64;
+        for (index, limb) in self.0.iter_mut().enumerate() {
+            let sum = *limb as u64 + *other.0.get(index).unwrap_or(&0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+        }
+        if carry > 0 { self.0.push(carry as u32) }
+    }
+
+    // Callers make sure other is no larger
+    fn sub(&mut self, other: &Self) {
+        let mut borrow = 0i64;
+        for (index, limb) in self.0.iter_mut().enumerate() {
+            let difference = *limb as i64 - *other.0.get(index).unwrap_or(&0) as i64 - borrow;
+            borrow = (difference < 0) as i64;
+            *limb = (difference + (borrow << 32)) as u32;
+        }
+        trim(&mut self.0);
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.len().cmp(&other.0.len()).then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+fn trim(limbs: &mut Vec<u32>) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rank::*;
+    use crate::test::{get_tiles, init_tiles, scripted_init};
+    use crate::tiles::Tile::*;
+    use rand::prelude::SliceRandom;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<WallRank>();
+        assert_send::<WallRankError>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<WallRank>();
+        assert_sync::<WallRankError>();
+    }
+
+    fn round_trip(init: &InitialState) -> WallRank {
+        let wall = init.wall_rank().unwrap();
+        let mut output = InitialState {
+            east_hand: vec![], south_hand: vec![], west_hand: vec![], north_hand: vec![], dead_wall: vec![], living_wall: vec![],
+            ..init.clone()
+        };
+        output.set_wall_rank(&wall).unwrap();
+        assert_eq!(&output, init);
+        wall
+    }
+
+    #[test]
+    fn shuffled_walls() {
+        let mut rng = Xoshiro256StarStar::from_seed([
+            1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0,
+            0, 0, 0,
+        ]);
+        for _ in 0..200 {
+            let mut tiles = get_tiles();
+            tiles.shuffle(&mut rng);
+            let mut init = InitialState::new();
+            init_tiles(tiles, &mut init);
+            let wall = round_trip(&init);
+            assert!(wall.rank.len() <= 78);
+        }
+
+        // The set order comes first among its own orderings, since get_tiles repeats each suit in sequence
+        let mut sorted = get_tiles();
+        sorted.sort_by_key(|tile| *tile as u8);
+        let mut init = InitialState::new();
+        init.east_hand = sorted;
+        assert_eq!(round_trip(&init).rank, Vec::<u8>::new());
+        round_trip(&scripted_init([&[], &[], &[], &[]], &[]));
+        round_trip(&InitialState::default());
+    }
+
+    #[test]
+    fn every_ordering() {
+        // Two hands of a small set, whose 5! / (2! 2!) orderings should each come out once, in order
+        let tiles = [PinOne, PinOne, PinTwo, SouOne, SouOne];
+        let init = InitialState { east_hand: tiles[..2].to_vec(), south_hand: tiles[2..].to_vec(), ..InitialState::default() };
+        let wall = init.wall_rank().unwrap();
+        let mut seen: Vec<Vec<u8>> = vec![];
+        for rank in 0..30u8 {
+            let mut output = InitialState::default();
+            output.set_wall_rank(&WallRank { rank: vec![rank], ..wall.clone() }).unwrap();
+            // Compared by value, since tiles of different suits have no order
+            let layout: Vec<u8> = output.east_hand.iter().chain(&output.south_hand).map(|&tile| tile as u8).collect();
+            let expected = if rank == 0 { vec![] } else { vec![rank] };
+            assert_eq!(output.wall_rank().unwrap().rank, expected);
+            assert!(seen.last().is_none_or(|last| *last < layout));
+            seen.push(layout);
+        }
+        assert_eq!(seen[0], tiles.map(|tile| tile as u8));
+        assert_eq!(seen[29], [SouOne, SouOne, PinTwo, PinOne, PinOne].map(|tile| tile as u8));
+
+        let mut output = InitialState::default();
+        assert_eq!(output.set_wall_rank(&WallRank { rank: vec![30], ..wall.clone() }), Err(WallRankError::RankTooLarge));
+        assert_eq!(output.set_wall_rank(&WallRank { lengths: [2, 2, 0, 0, 0, 0], ..wall }), Err(WallRankError::LengthMismatch));
+        assert_eq!(output, InitialState::default());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut value = Natural::from_be_bytes(&[0, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(value.to_be_bytes(), vec![1, 2, 3, 4, 5]);
+        value.mul_small(u32::MAX);
+        value.div_small(u32::MAX);
+        assert_eq!(value.to_be_bytes(), vec![1, 2, 3, 4, 5]);
+        let mut sum = value.clone();
+        sum.add(&Natural::from_be_bytes(&[0xff, 0xff, 0xff, 0xff]));
+        assert_eq!(sum.to_be_bytes(), vec![2, 2, 3, 4, 4]);
+        sum.sub(&value);
+        assert_eq!(sum.to_be_bytes(), vec![0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(value.cmp(&sum), Ordering::Greater);
+        // 136! / (4!^31 3!^3), with one red five of each suit
+        let mut counts = [0; TILE_VALUES];
+        get_tiles().iter().for_each(|&tile| counts[tile as usize] += 1);
+        assert_eq!(orderings(&counts).to_be_bytes().len(), 78);
+    }
+}
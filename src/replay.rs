@@ -0,0 +1,1169 @@
+use std::fmt;
+
+use crate::abortive::{self, AbortiveDraw};
+use crate::actions::Action;
+use crate::agari;
+use crate::events::Event;
+use crate::furiten::{self, Furiten};
+use crate::mode::Mode;
+use crate::ruleset::{KanDora, MultipleRon, Ruleset};
+use crate::ryuukyoku::ExhaustiveDraw;
+use crate::dead_wall::DeadWall;
+use crate::tiles::{Suit, Tile};
+use crate::tile_or_action::{DecodeError, TileOrAction};
+use crate::waits::{self, WaitingTile};
+use crate::yaku::{self, Win, WinContext};
+use crate::{InitialState, Wind};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum RuleError {
+    // A stored byte that is neither a tile nor a defined action
+    InvalidByte(u8),
+    HandEnded,
+    WallExhausted,
+    UnexpectedDraw,
+    UnexpectedDiscard,
+    UnexpectedAction(Action),
+    WrongDraw { expected: Tile, found: Tile },
+    TileNotHeld(Tile),
+    InvalidMeld(Action),
+    InRiichi,
+    OpenHand,
+    NotTenpai,
+    // Riichi needs a draw still to come
+    TooFewTiles,
+    IncompleteHand,
+    NoYaku,
+    // Kyuushu kyuuhai needs nine kinds of terminals and honors on an uninterrupted first turn
+    NotKyuushu,
+    // Under rules without atozuke, some wait of the winning hand would have had no yaku
+    Atozuke,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::InvalidByte(raw) => write!(f, "byte {raw:#010b} is not a tile or action"),
+            RuleError::HandEnded => write!(f, "the hand has already ended"),
+            RuleError::WallExhausted => write!(f, "no tiles remain to draw"),
+            RuleError::UnexpectedDraw => write!(f, "no draw is expected here"),
+            RuleError::UnexpectedDiscard => write!(f, "no discard is expected here"),
+            RuleError::UnexpectedAction(action) => write!(f, "{action:?} is not allowed here"),
+            RuleError::WrongDraw { expected, found } => write!(f, "drew {found:?} but the wall holds {expected:?}"),
+            RuleError::TileNotHeld(tile) => write!(f, "{tile:?} is not in the acting hand"),
+            RuleError::InvalidMeld(action) => write!(f, "the hand cannot form {action:?}"),
+            RuleError::InRiichi => write!(f, "the player is in riichi"),
+            RuleError::OpenHand => write!(f, "the hand is open"),
+            RuleError::NotTenpai => write!(f, "the hand is not tenpai"),
+            RuleError::TooFewTiles => write!(f, "fewer than {RIICHI_TILES} tiles remain to draw"),
+            RuleError::IncompleteHand => write!(f, "the hand is not complete"),
+            RuleError::NoYaku => write!(f, "the hand has no yaku"),
+            RuleError::NotKyuushu => write!(f, "the hand cannot be abandoned as kyuushu kyuuhai"),
+            RuleError::Atozuke => write!(f, "the hand does not have a yaku on every wait"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl From<DecodeError> for RuleError {
+    fn from(error: DecodeError) -> Self {
+        RuleError::InvalidByte(error.byte())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MeldKind {
+    Chii,
+    Pon,
+    CalledKan,
+    ClosedKan,
+    AddedKan,
+}
+
+// Every tile in the meld, including the claimed one, which is absent for closed kans
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Meld {
+    pub kind: MeldKind,
+    pub tiles: Vec<Tile>,
+    pub called: Option<Tile>,
+    pub from: Option<Wind>,
+}
+
+impl Meld {
+    pub fn is_kan(&self) -> bool {
+        matches!(self.kind, MeldKind::CalledKan | MeldKind::ClosedKan | MeldKind::AddedKan)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct RiverTile {
+    pub tile: Tile,
+    // Turned sideways to declare riichi
+    pub riichi: bool,
+    // Claimed by another player, leaving it out of the river proper
+    pub called: bool,
+}
+
+// The living wall must still hold this many tiles when riichi is declared
+pub(crate) const RIICHI_TILES: usize = 4;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum Phase {
+    // The seat is due to draw, from the dead wall after a kan
+    Draw { seat: Wind, rinshan: bool },
+    // The seat holds a full hand and must discard, only discard after a chii or pon
+    Act { seat: Wind, after_call: bool },
+    // The tile may be claimed before the next seat draws
+    Discarded { seat: Wind, tile: Tile },
+    // An added kan may be robbed before the replacement draw
+    AddedKan { seat: Wind, tile: Tile },
+    // One or more players have called ron on the tile, and further rons may follow
+    Ron { seat: Wind, tile: Tile, winners: [bool; 4], robbed: bool },
+    Ended,
+}
+
+// A snapshot of the table, advanced one stored byte at a time while rejecting anything the rules forbid.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct GameState {
+    mode: Mode,
+    ruleset: Ruleset,
+    actor: Wind,
+    prevailing_wind: Wind,
+    repeat_count: u8,
+    honba: u8,
+    riichi_sticks: u8,
+    scores: Vec<i32>,
+    hands: [Vec<Tile>; 4],
+    rivers: [Vec<RiverTile>; 4],
+    melds: [Vec<Meld>; 4],
+    kita: [u8; 4],
+    riichi: [bool; 4],
+    open_riichi: [bool; 4],
+    riichi_pending: bool,
+    double_riichi: [bool; 4],
+    ippatsu: [bool; 4],
+    // Set for seats that let a winning tile go by, since their last discard and since riichi
+    passed: [bool; 4],
+    passed_in_riichi: [bool; 4],
+    living_wall: Vec<Tile>,
+    wall_next: usize,
+    wall_end: usize,
+    dead_wall: DeadWall,
+    rinshan_draws: usize,
+    dora_pending: usize,
+    rinshan_draw: bool,
+    phase: Phase,
+    wins: Vec<Win>,
+    // Set as soon as the table calls for an abortive draw, which a ron on the last discard still overrides
+    abort: Option<AbortiveDraw>,
+}
+
+impl GameState {
+    pub fn new(init: &InitialState) -> Self {
+        Self::new_with_ruleset(init, Ruleset::default())
+    }
+
+    pub fn new_with_ruleset(init: &InitialState, ruleset: Ruleset) -> Self {
+        Self {
+            mode: init.mode,
+            ruleset,
+            actor: Wind::East,
+            prevailing_wind: init.prevailing_wind,
+            repeat_count: init.repeat_count,
+            honba: init.hanba_count,
+            riichi_sticks: init.unclaimed_riichi_count,
+            scores: init.scores.clone(),
+            hands: [
+                init.east_hand.clone(),
+                init.south_hand.clone(),
+                init.west_hand.clone(),
+                init.north_hand.clone(),
+            ],
+            rivers: Default::default(),
+            melds: Default::default(),
+            kita: [0; 4],
+            riichi: [false; 4],
+            open_riichi: [false; 4],
+            riichi_pending: false,
+            double_riichi: [false; 4],
+            ippatsu: [false; 4],
+            passed: [false; 4],
+            passed_in_riichi: [false; 4],
+            living_wall: init.living_wall.clone(),
+            wall_next: 0,
+            wall_end: init.living_wall.len(),
+            dead_wall: DeadWall::new_for(&init.dead_wall, init.mode),
+            rinshan_draws: 0,
+            dora_pending: 0,
+            rinshan_draw: false,
+            phase: Phase::Draw { seat: Wind::East, rinshan: false },
+            wins: vec![],
+            abort: None,
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    // The seat that made the most recent move, or East before anything has happened
+    pub fn actor(&self) -> Wind {
+        self.actor
+    }
+
+    pub fn hand(&self, seat: Wind) -> &[Tile] {
+        &self.hands[seat.index()]
+    }
+
+    pub fn river(&self, seat: Wind) -> &[RiverTile] {
+        &self.rivers[seat.index()]
+    }
+
+    pub fn melds(&self, seat: Wind) -> &[Meld] {
+        &self.melds[seat.index()]
+    }
+
+    // What the seat waits on, which is only ever non-empty while it holds a hand one tile short of a win
+    pub fn waits(&self, seat: Wind) -> Vec<WaitingTile> {
+        waits::waits(&self.hands[seat.index()], &self.melds[seat.index()])
+    }
+
+    // Declined rons are never recorded, so any winning tile another seat discarded or added to a kan
+    // without this seat calling ron counts as passed over.
+    pub fn furiten(&self, seat: Wind) -> Furiten {
+        Furiten {
+            permanent: furiten::is_permanent(&self.rivers[seat.index()], &self.waits(seat)),
+            temporary: self.passed[seat.index()],
+            riichi: self.passed_in_riichi[seat.index()],
+        }
+    }
+
+    // North tiles the seat has set aside in sanma
+    pub fn kita(&self, seat: Wind) -> u8 {
+        self.kita[seat.index()]
+    }
+
+    pub fn is_riichi(&self, seat: Wind) -> bool {
+        self.riichi[seat.index()]
+    }
+
+    // Declared with the hand shown, once the riichi stands
+    pub fn is_open_riichi(&self, seat: Wind) -> bool {
+        self.riichi[seat.index()] && self.open_riichi[seat.index()]
+    }
+
+    // Tiles still to be drawn, in draw order
+    pub fn living_wall(&self) -> &[Tile] {
+        &self.living_wall[self.wall_next..self.wall_end]
+    }
+
+    // Kan dora are added as they are flipped, which by default is after the next discard for open and added kans
+    pub fn dead_wall(&self) -> &DeadWall {
+        &self.dead_wall
+    }
+
+    pub fn dora_indicators(&self) -> &[Tile] {
+        self.dead_wall.dora_indicators()
+    }
+
+    // Every win declared so far, with the yaku it was counted on
+    pub fn wins(&self) -> &[Win] {
+        &self.wins
+    }
+
+    // Why the hand ended without a winner, if it did
+    pub fn abortive_draw(&self) -> Option<AbortiveDraw> {
+        if self.is_ended() { self.abort } else { None }
+    }
+
+    // The settlement once the wall runs out with nobody winning, which is never the case after an abortive draw
+    pub fn exhaustive_draw(&self) -> Option<ExhaustiveDraw> {
+        let Phase::Discarded { .. } = self.phase else { return None };
+        if self.wall_next < self.wall_end || self.abort.is_some() { return None }
+
+        let tenpai = [Wind::East, Wind::South, Wind::West, Wind::North].map(|seat| self.waits(seat).iter().any(|wait| !wait.karaten));
+        Some(ExhaustiveDraw::new(self.mode, tenpai, &self.rivers, self.riichi, (self.repeat_count, self.honba, self.riichi_sticks)))
+    }
+
+    // The points each seat gains or loses once the hand has been won, including honba and every riichi
+    // stick. Both go to the first winner counting round from the discarder.
+    pub fn score_deltas(&self) -> Option<[i32; 4]> {
+        if !self.is_ended() || self.wins.is_empty() { return None }
+
+        let mut deltas = [0; 4];
+        for win in &self.wins {
+            deltas.iter_mut().zip(win.payments()).for_each(|(delta, payment)| *delta += payment);
+        }
+
+        let honba = self.honba as i32;
+        let first = match self.wins[0].from {
+            Some(discarder) => {
+                let mut seat = self.mode.next(discarder);
+                while !self.wins.iter().any(|win| win.seat == seat) {
+                    seat = self.mode.next(seat);
+                }
+                deltas[discarder.index()] -= 300 * honba;
+                deltas[seat.index()] += 300 * honba;
+                seat
+            },
+            None => {
+                let seat = self.wins[0].seat;
+                for payer in self.mode.seats().iter().filter(|&&payer| payer != seat) {
+                    deltas[payer.index()] -= 100 * honba;
+                    deltas[seat.index()] += 100 * honba;
+                }
+                seat
+            },
+        };
+
+        let declared = self.riichi.iter().filter(|&&riichi| riichi).count() as i32;
+        for (delta, _) in deltas.iter_mut().zip(self.riichi).filter(|(_, riichi)| *riichi) {
+            *delta -= 1000;
+        }
+        deltas[first.index()] += 1000 * (self.riichi_sticks as i32 + declared);
+        Some(deltas)
+    }
+
+    // Score changes however the hand ended. After an abortive draw only the riichi sticks placed move,
+    // onto the table, including the fourth riichi that caused one.
+    pub fn settlement_deltas(&self) -> Option<[i32; 4]> {
+        if !self.is_ended() { return None }
+        if let Some(deltas) = self.score_deltas() { return Some(deltas) }
+        if let Some(draw) = self.exhaustive_draw() { return Some(draw.deltas) }
+
+        let mut deltas = [0; 4];
+        for (delta, _) in deltas.iter_mut().zip(self.riichi).filter(|(_, riichi)| *riichi) {
+            *delta -= 1000;
+        }
+        if self.abort == Some(AbortiveDraw::SuuchaRiichi) {
+            deltas[self.actor.index()] -= 1000;
+        }
+        Some(deltas)
+    }
+
+    // Every seat's score once the hand has ended, if the deal recorded where they started
+    pub fn final_scores(&self) -> Option<Vec<i32>> {
+        if self.scores.len() != self.mode.seats().len() { return None }
+        let deltas = self.settlement_deltas()?;
+        Some(self.scores.iter().zip(deltas).map(|(score, delta)| score + delta).collect())
+    }
+
+    pub fn is_ended(&self) -> bool {
+        match self.phase {
+            Phase::Ron { .. } | Phase::Ended => true,
+            Phase::Discarded { .. } => self.wall_next >= self.wall_end || self.abort.is_some(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn expects_draw(&self) -> bool {
+        match self.phase {
+            Phase::Draw { .. } | Phase::AddedKan { .. } => true,
+            Phase::Discarded { .. } => self.wall_next < self.wall_end && self.abort.is_none(),
+            _ => false,
+        }
+    }
+
+    // Whether the next stored byte answers a discard, which is the is_call context TileOrAction needs
+    pub fn expects_call(&self) -> bool {
+        matches!(self.phase, Phase::Discarded { .. })
+    }
+
+    pub(crate) fn expects_discard(&self) -> bool {
+        matches!(self.phase, Phase::Act { .. })
+    }
+
+    // The tile the next draw must be, from the dead wall when a kan or kita calls for a replacement
+    pub(crate) fn next_draw(&self) -> Option<Tile> {
+        if self.wall_next >= self.wall_end { return None }
+        match self.phase {
+            Phase::Draw { rinshan: true, .. } | Phase::AddedKan { .. } => Some(self.rinshan_tile()),
+            Phase::Draw { rinshan: false, .. } => Some(self.living_wall[self.wall_next]),
+            Phase::Discarded { .. } if self.abort.is_none() => Some(self.living_wall[self.wall_next]),
+            _ => None,
+        }
+    }
+
+    // Whether the stored byte can only come after a draw, which is anything but a call on the tile in play
+    pub(crate) fn needs_draw_before(&self, raw: u8) -> bool {
+        let is_ron = raw >> 2 == Action::CallRonByRight as u8 >> 2;
+        let is_call = raw >> 6 == 0b01 || (!Action::has_tile(raw) && raw >> 2 < 0b11_1111);
+        match self.phase {
+            Phase::Draw { .. } => true,
+            Phase::AddedKan { .. } => !is_ron,
+            Phase::Discarded { .. } => self.expects_draw() && !is_call,
+            _ => false,
+        }
+    }
+
+    // Applies one stored byte, resolving what it means from the state of the table
+    pub fn step(&mut self, raw: u8) -> Result<Event, RuleError> {
+        // Only the 01 prefix reads differently when answering a discard
+        let is_call = self.expects_call() && raw >> 6 == 0b01;
+        let (action, tile) = TileOrAction::new_unchecked(raw).decode(is_call)?;
+
+        match action {
+            Action::None => self.step_tile(tile),
+            // Chii after a discard, or a kan declared from a full hand
+            Action::CallChiiOrDeclareKan | Action::CallChiiWithRedFive => match self.phase {
+                Phase::Discarded { seat, tile: called } => self.chii(seat, called, tile, action),
+                Phase::Act { seat, after_call: false } => self.declare_kan(seat, tile),
+                _ => Err(self.unexpected(action)),
+            },
+            Action::DeclareRiichi => match self.phase {
+                Phase::Act { seat, after_call: false } => self.declare_riichi(seat, tile, false),
+                _ => Err(self.unexpected(action)),
+            },
+            Action::DeclareOpenRiichi => match self.phase {
+                Phase::Act { seat, after_call: false } if self.ruleset.open_riichi => self.declare_riichi(seat, tile, true),
+                _ => Err(self.unexpected(action)),
+            },
+            _ => self.step_action(action),
+        }
+    }
+
+    fn unexpected(&self, action: Action) -> RuleError {
+        match self.phase {
+            Phase::Ended | Phase::Ron { .. } => RuleError::HandEnded,
+            _ => RuleError::UnexpectedAction(action),
+        }
+    }
+
+    fn step_tile(&mut self, tile: Tile) -> Result<Event, RuleError> {
+        match self.phase {
+            Phase::Draw { seat, rinshan } => self.draw(seat, rinshan, tile),
+            Phase::AddedKan { seat, tile: added } => {
+                self.pass_tile(seat, added);
+                self.draw(seat, true, tile)
+            },
+            Phase::Discarded { seat, .. } => {
+                if self.abort.is_some() { return Err(RuleError::HandEnded) }
+                self.pass_discard();
+                self.draw(self.mode.next(seat), false, tile)
+            },
+            Phase::Act { seat, .. } => self.discard(seat, tile),
+            Phase::Ron { .. } | Phase::Ended => Err(RuleError::HandEnded),
+        }
+    }
+
+    fn step_action(&mut self, action: Action) -> Result<Event, RuleError> {
+        match action {
+            Action::CallPonByRight | Action::CallPonByOpposite | Action::CallPonByLeft |
+            Action::CallPonByRightWithRedFive | Action::CallPonByOppositeWithRedFive | Action::CallPonByLeftWithRedFive |
+            Action::CallKanByRight | Action::CallKanByOpposite | Action::CallKanByLeft => {
+                let Phase::Discarded { seat, tile } = self.phase else { return Err(self.unexpected(action)) };
+                let caller = self.mode.relative(seat, action.get_player_unchecked()).ok_or(RuleError::UnexpectedAction(action))?;
+                self.call(caller, seat, tile, action)
+            },
+            Action::CallRonByRight | Action::CallRonByOpposite | Action::CallRonByLeft => {
+                let (seat, tile, mut winners, robbed) = match self.phase {
+                    Phase::Discarded { seat, tile } => (seat, tile, [false; 4], false),
+                    Phase::AddedKan { seat, tile } => (seat, tile, [false; 4], true),
+                    Phase::Ron { seat, tile, winners, robbed } => (seat, tile, winners, robbed),
+                    _ => return Err(self.unexpected(action)),
+                };
+                let winner = self.mode.relative(seat, action.get_player_unchecked()).ok_or(RuleError::UnexpectedAction(action))?;
+                if winners[winner.index()] { return Err(RuleError::UnexpectedAction(action)) }
+                // With atamahane the first ron recorded is the only one
+                if self.ruleset.multiple_ron == MultipleRon::Single && winners.contains(&true) {
+                    return Err(RuleError::UnexpectedAction(action))
+                }
+
+                let mut concealed = self.hands[winner.index()].clone();
+                concealed.push(tile);
+                let mut context = self.win_context(winner, tile, Some(seat));
+                context.last_tile &= !robbed;
+                context.chankan = robbed;
+                let mut win = yaku::evaluate(&concealed, &self.melds[winner.index()], &context)?;
+                self.check_atozuke(winner, &context)?;
+                win.furiten = self.furiten(winner).any();
+
+                // A riichi declared on the winning tile never takes effect, and a ron beats any abortive draw
+                self.riichi_pending = false;
+                self.abort = None;
+                winners[winner.index()] = true;
+                self.actor = winner;
+                if winners.iter().filter(|&&won| won).count() == 3 && self.ruleset.multiple_ron == MultipleRon::Double {
+                    self.wins.clear();
+                    self.abort = Some(AbortiveDraw::SanchaHou);
+                    self.phase = Phase::Ended;
+                    return Ok(Event::AbortiveDraw { seat: winner })
+                }
+                self.wins.push(win);
+                self.phase = Phase::Ron { seat, tile, winners, robbed };
+                Ok(Event::Ron { seat: winner, tile, from: seat })
+            },
+            Action::DeclareTsumo => {
+                let Phase::Act { seat, after_call: false } = self.phase else { return Err(self.unexpected(action)) };
+                let hand = &self.hands[seat.index()];
+                let context = self.win_context(seat, *hand.last().unwrap(), None);
+                let win = yaku::evaluate(hand, &self.melds[seat.index()], &context)?;
+                self.check_atozuke(seat, &context)?;
+                self.wins.push(win);
+                self.phase = Phase::Ended;
+                Ok(Event::Tsumo { seat })
+            },
+            Action::DeclareMulligan => {
+                let Phase::Act { seat, after_call: false } = self.phase else { return Err(self.unexpected(action)) };
+                if !self.is_first_turn(seat) || !abortive::is_kyuushu(&self.hands[seat.index()]) {
+                    return Err(RuleError::NotKyuushu)
+                }
+                self.actor = seat;
+                self.abort = Some(AbortiveDraw::KyuushuKyuuhai);
+                self.phase = Phase::Ended;
+                Ok(Event::AbortiveDraw { seat })
+            },
+            Action::DeclareKita => match self.phase {
+                Phase::Act { seat, after_call: false } if self.mode == Mode::Sanma => self.declare_kita(seat),
+                _ => Err(self.unexpected(action)),
+            },
+            _ => Err(self.unexpected(action)),
+        }
+    }
+
+    // Without atozuke every tile the hand waits on must win with a yaku, not just the one it won on
+    fn check_atozuke(&self, seat: Wind, context: &WinContext) -> Result<(), RuleError> {
+        if self.ruleset.atozuke { return Ok(()) }
+
+        let mut hand = self.hands[seat.index()].clone();
+        if context.from.is_none() {
+            let position = hand.iter().rposition(|tile| *tile == context.tile).unwrap();
+            hand.remove(position);
+        }
+        let melds = &self.melds[seat.index()];
+        for wait in waits::waits(&hand, melds).into_iter().filter(|wait| !wait.karaten) {
+            let mut concealed = hand.clone();
+            concealed.push(wait.tile);
+            let context = WinContext { tile: wait.tile, ..*context };
+            if yaku::evaluate(&concealed, melds, &context).is_err() { return Err(RuleError::Atozuke) }
+        }
+        Ok(())
+    }
+
+    fn win_context(&self, seat: Wind, tile: Tile, from: Option<Wind>) -> WinContext {
+        let riichi = self.riichi[seat.index()];
+        let mut tiles = self.hands[seat.index()].clone();
+        if from.is_some() { tiles.push(tile) }
+        tiles.extend(self.melds[seat.index()].iter().flat_map(|meld| meld.tiles.iter()));
+        // Kita still count as dora when north is pointed to
+        let kita = self.kita[seat.index()];
+        tiles.extend(std::iter::repeat_n(Tile::HonorNorth, kita as usize));
+        let ura_dora = if riichi { self.dead_wall.count_ura_dora(&tiles) } else { 0 };
+
+        WinContext {
+            seat,
+            prevailing_wind: self.prevailing_wind,
+            tile,
+            from,
+            riichi,
+            double_riichi: riichi && self.double_riichi[seat.index()],
+            open_riichi: riichi && self.open_riichi[seat.index()],
+            ippatsu: self.ippatsu[seat.index()],
+            last_tile: self.wall_next >= self.wall_end,
+            rinshan: from.is_none() && self.rinshan_draw,
+            chankan: false,
+            first_draw: from.is_none() && self.is_first_turn(seat),
+            dora: self.dead_wall.count_dora(&tiles),
+            red_fives: tiles.iter().filter(|tile| tile.is_red_five()).count() as u8,
+            ura_dora,
+            kita,
+            mode: self.mode,
+            ruleset: self.ruleset,
+        }
+    }
+
+    // The seat has yet to discard, and nobody has called or declared anything, kita included
+    fn is_first_turn(&self, seat: Wind) -> bool {
+        self.rivers[seat.index()].is_empty() && self.melds.iter().all(Vec::is_empty) && self.kita == [0; 4]
+    }
+
+    fn draw(&mut self, seat: Wind, rinshan: bool, tile: Tile) -> Result<Event, RuleError> {
+        if self.wall_next >= self.wall_end { return Err(RuleError::WallExhausted) }
+
+        let expected = if rinshan { self.rinshan_tile() } else { self.living_wall[self.wall_next] };
+        if expected != tile { return Err(RuleError::WrongDraw { expected, found: tile }) }
+
+        if rinshan {
+            // The dead wall is topped back up from the end of the living wall
+            self.rinshan_draws += 1;
+            self.wall_end -= 1;
+            // Any kan or kita breaks every ippatsu, but an added kan only once it survives being robbed
+            self.ippatsu = [false; 4];
+        }
+        else {
+            self.wall_next += 1;
+        }
+
+        self.actor = seat;
+        self.rinshan_draw = rinshan;
+        self.hands[seat.index()].push(tile);
+        self.phase = Phase::Act { seat, after_call: false };
+        Ok(Event::Draw { seat, tile, rinshan })
+    }
+
+    // The first four replacement draws come from the front of the dead wall, and any later
+    // ones from the tiles that replenished it.
+    fn rinshan_tile(&self) -> Tile {
+        match self.dead_wall.rinshan_tiles().get(self.rinshan_draws) {
+            Some(tile) => *tile,
+            _ => self.living_wall[self.wall_end - 1],
+        }
+    }
+
+    fn discard(&mut self, seat: Wind, tile: Tile) -> Result<Event, RuleError> {
+        let hand = &mut self.hands[seat.index()];
+        let position = hand.iter().rposition(|held| *held == tile).ok_or(RuleError::TileNotHeld(tile))?;
+        // After riichi only the drawn tile may go
+        if self.riichi[seat.index()] && position != hand.len() - 1 { return Err(RuleError::InRiichi) }
+        hand.remove(position);
+        self.ippatsu[seat.index()] = false;
+        self.passed[seat.index()] = false;
+        self.rivers[seat.index()].push(RiverTile { tile, riichi: false, called: false });
+        // Kan dora held back by the ruleset are only flipped once the replacement tile is discarded
+        self.reveal_dora();
+        if self.is_suufon_renda() { self.abort = Some(AbortiveDraw::SuufonRenda) }
+        if self.is_suukaikan() { self.abort = Some(AbortiveDraw::Suukaikan) }
+        self.actor = seat;
+        self.phase = Phase::Discarded { seat, tile };
+        Ok(Event::Discard { seat, tile })
+    }
+
+    // Every seat's only discard is the same wind, with nothing called in between
+    fn is_suufon_renda(&self) -> bool {
+        let Some(first) = self.rivers[0].first() else { return false };
+        first.tile.suit() == Some(Suit::Wind)
+            && self.rivers.iter().all(|river| matches!(river.as_slice(), [only] if only.tile == first.tile))
+            && self.melds.iter().all(Vec::is_empty)
+    }
+
+    // Four kans split between seats end the hand, while one seat holding all four may still win with suukantsu
+    fn is_suukaikan(&self) -> bool {
+        let kans = self.melds.iter().map(|melds| melds.iter().filter(|meld| meld.is_kan()).count());
+        self.kan_count() >= 4 && kans.max() != Some(4)
+    }
+
+    fn kan_count(&self) -> usize {
+        self.melds.iter().flatten().filter(|meld| meld.is_kan()).count()
+    }
+
+    // Flips the new kan dora now or once the replacement tile is discarded, as the ruleset says
+    fn flip_kan_dora(&mut self, closed: bool) {
+        match (self.ruleset.kan_dora, closed) {
+            (KanDora::Immediate, _) | (KanDora::Mixed, true) => self.dead_wall.reveal(1),
+            (KanDora::Delayed, _) | (KanDora::Mixed, false) => self.dora_pending += 1,
+        }
+    }
+
+    fn reveal_dora(&mut self) {
+        self.dead_wall.reveal(self.dora_pending);
+        self.dora_pending = 0;
+    }
+
+    // Nobody claimed the last discard, so any riichi declared with it now stands
+    fn pass_discard(&mut self) {
+        let Phase::Discarded { seat, tile } = self.phase else { return };
+        if self.riichi_pending {
+            self.riichi[seat.index()] = true;
+            self.ippatsu[seat.index()] = true;
+        }
+        self.riichi_pending = false;
+        self.pass_tile(seat, tile);
+    }
+
+    fn pass_tile(&mut self, discarder: Wind, tile: Tile) {
+        let mut seat = self.mode.next(discarder);
+        while seat != discarder {
+            let mut concealed = self.hands[seat.index()].clone();
+            concealed.push(tile);
+            if agari::is_complete(&concealed, self.melds[seat.index()].len()) {
+                self.passed[seat.index()] = true;
+                self.passed_in_riichi[seat.index()] |= self.riichi[seat.index()];
+            }
+            seat = self.mode.next(seat);
+        }
+    }
+
+    fn declare_riichi(&mut self, seat: Wind, tile: Tile, open: bool) -> Result<Event, RuleError> {
+        if self.riichi[seat.index()] { return Err(RuleError::InRiichi) }
+        if !self.is_closed(seat) { return Err(RuleError::OpenHand) }
+        if self.living_wall().len() < RIICHI_TILES { return Err(RuleError::TooFewTiles) }
+
+        let mut after = self.hands[seat.index()].clone();
+        let position = after.iter().position(|held| *held == tile).ok_or(RuleError::TileNotHeld(tile))?;
+        after.remove(position);
+        if !waits::is_tenpai(&after, &self.melds[seat.index()]) { return Err(RuleError::NotTenpai) }
+
+        let double = self.is_first_turn(seat);
+        self.discard(seat, tile)?;
+        self.double_riichi[seat.index()] = double;
+        self.open_riichi[seat.index()] = open;
+        if let Some(discard) = self.rivers[seat.index()].last_mut() {
+            discard.riichi = true;
+        }
+        self.riichi_pending = true;
+        // The fourth riichi ends the hand as soon as its discard passes, so it never has to stand
+        if self.riichi.iter().filter(|&&riichi| riichi).count() == 3 {
+            self.abort = Some(AbortiveDraw::SuuchaRiichi);
+        }
+        Ok(Event::Riichi { seat, discard: tile, open })
+    }
+
+    // Sets a north aside and draws its replacement from the dead wall, like a closed kan without the dora
+    fn declare_kita(&mut self, seat: Wind) -> Result<Event, RuleError> {
+        let hand = &self.hands[seat.index()];
+        let position = hand.iter().rposition(|tile| *tile == Tile::HonorNorth).ok_or(RuleError::TileNotHeld(Tile::HonorNorth))?;
+        if self.riichi[seat.index()] && position != hand.len() - 1 { return Err(RuleError::InRiichi) }
+        if self.wall_next >= self.wall_end { return Err(RuleError::WallExhausted) }
+
+        self.hands[seat.index()].remove(position);
+        self.kita[seat.index()] += 1;
+        self.actor = seat;
+        self.phase = Phase::Draw { seat, rinshan: true };
+        Ok(Event::Kita { seat })
+    }
+
+    fn is_closed(&self, seat: Wind) -> bool {
+        self.melds[seat.index()].iter().all(|meld| meld.kind == MeldKind::ClosedKan)
+    }
+
+    fn chii(&mut self, discarder: Wind, called: Tile, lowest: Tile, action: Action) -> Result<Event, RuleError> {
+        // Sanma is played without chii
+        if self.mode == Mode::Sanma { return Err(RuleError::UnexpectedAction(action)) }
+        let caller = discarder.next();
+        self.check_can_call(caller, action)?;
+
+        let invalid = RuleError::InvalidMeld(action);
+        let start = lowest.kind().filter(|&kind| lowest == lowest.normalized() && agari::is_sequence_start(kind)).ok_or(invalid)?;
+        let called_kind = called.kind().ok_or(invalid)?;
+        if !(start..start + 3).contains(&called_kind) { return Err(invalid) }
+
+        let needed: Vec<usize> = (start..start + 3).filter(|&kind| kind != called_kind).collect();
+        let mut tiles = self.take_from_hand(caller, &needed, action)?;
+        tiles.push(called);
+        let mut sequence = [tiles[0], tiles[1], tiles[2]];
+        sequence.sort_by_key(|tile| tile.kind());
+
+        self.claim_discard(discarder);
+        self.melds[caller.index()].push(Meld { kind: MeldKind::Chii, tiles, called: Some(called), from: Some(discarder) });
+        self.actor = caller;
+        self.phase = Phase::Act { seat: caller, after_call: true };
+        Ok(Event::Chii { seat: caller, tiles: sequence, from: discarder })
+    }
+
+    fn call(&mut self, caller: Wind, discarder: Wind, called: Tile, action: Action) -> Result<Event, RuleError> {
+        if caller == discarder { return Err(RuleError::UnexpectedAction(action)) }
+        self.check_can_call(caller, action)?;
+
+        let kind = called.kind().ok_or(RuleError::InvalidMeld(action))?;
+        let is_kan = matches!(action, Action::CallKanByRight | Action::CallKanByOpposite | Action::CallKanByLeft);
+        // The wall holds no more than four kans
+        if is_kan && self.kan_count() >= 4 { return Err(RuleError::InvalidMeld(action)) }
+        let needed = if is_kan { vec![kind; 3] } else { vec![kind; 2] };
+        let mut tiles = self.take_from_hand(caller, &needed, action)?;
+        tiles.push(called);
+
+        let (kind, phase, event) = if is_kan {
+            self.reveal_dora();
+            self.flip_kan_dora(false);
+            let event = Event::Daiminkan { seat: caller, tile: called, from: discarder };
+            (MeldKind::CalledKan, Phase::Draw { seat: caller, rinshan: true }, event)
+        }
+        else {
+            let red = tiles.iter().take(2).any(|tile| tile.is_red_five());
+            let event = Event::Pon { seat: caller, tile: called, from: discarder, red };
+            (MeldKind::Pon, Phase::Act { seat: caller, after_call: true }, event)
+        };
+        self.claim_discard(discarder);
+        self.melds[caller.index()].push(Meld { kind, tiles, called: Some(called), from: Some(discarder) });
+        self.actor = caller;
+        self.phase = phase;
+        Ok(event)
+    }
+
+    fn claim_discard(&mut self, discarder: Wind) {
+        self.ippatsu = [false; 4];
+        if let Some(discard) = self.rivers[discarder.index()].last_mut() {
+            discard.called = true;
+        }
+    }
+
+    fn check_can_call(&mut self, caller: Wind, action: Action) -> Result<(), RuleError> {
+        // The final discard of the hand may only be claimed with ron
+        if self.wall_next >= self.wall_end { return Err(RuleError::UnexpectedAction(action)) }
+        if self.abort.is_some() { return Err(RuleError::HandEnded) }
+        if self.riichi[caller.index()] { return Err(RuleError::InRiichi) }
+        self.pass_discard();
+        Ok(())
+    }
+
+    // Removes one tile of each needed kind from the caller's hand. Red fives are taken first
+    // when the action says one was used and last otherwise, so the encoding stays canonical.
+    fn take_from_hand(&mut self, caller: Wind, needed: &[usize], action: Action) -> Result<Vec<Tile>, RuleError> {
+        let with_red = matches!(action,
+            Action::CallChiiWithRedFive |
+            Action::CallPonByRightWithRedFive |
+            Action::CallPonByOppositeWithRedFive |
+            Action::CallPonByLeftWithRedFive
+        );
+        let is_kan = matches!(action, Action::CallKanByRight | Action::CallKanByOpposite | Action::CallKanByLeft);
+
+        let mut hand = self.hands[caller.index()].clone();
+        let mut taken = vec![];
+        for &kind in needed {
+            let position = hand.iter()
+                .enumerate()
+                .filter(|(_, tile)| tile.kind() == Some(kind))
+                .max_by_key(|(_, tile)| tile.is_red_five() == with_red)
+                .map(|(position, _)| position)
+                .ok_or(RuleError::InvalidMeld(action))?;
+            taken.push(hand.remove(position));
+        }
+
+        if !is_kan && taken.iter().any(|tile| tile.is_red_five()) != with_red {
+            return Err(RuleError::InvalidMeld(action))
+        }
+
+        self.hands[caller.index()] = hand;
+        Ok(taken)
+    }
+
+    fn declare_kan(&mut self, seat: Wind, tile: Tile) -> Result<Event, RuleError> {
+        let action = Action::CallChiiOrDeclareKan;
+        let kind = tile.kind().ok_or(RuleError::InvalidMeld(action))?;
+        if self.wall_next >= self.wall_end { return Err(RuleError::WallExhausted) }
+        if self.kan_count() >= 4 { return Err(RuleError::InvalidMeld(action)) }
+
+        let added = self.melds[seat.index()].iter().position(|meld| meld.kind == MeldKind::Pon && meld.tiles[0].kind() == Some(kind));
+        if let Some(meld) = added {
+            if self.riichi[seat.index()] { return Err(RuleError::InRiichi) }
+            let taken = self.take_all_from_hand(seat, kind, 1).ok_or(RuleError::InvalidMeld(action))?;
+            let meld = &mut self.melds[seat.index()][meld];
+            meld.kind = MeldKind::AddedKan;
+            meld.tiles.extend(taken);
+            // A kan declared before an earlier kan's dora was flipped flips it now
+            self.reveal_dora();
+            self.flip_kan_dora(false);
+            self.actor = seat;
+            self.phase = Phase::AddedKan { seat, tile };
+            return Ok(Event::Shouminkan { seat, tile })
+        }
+
+        if self.riichi[seat.index()] && !self.keeps_waits(seat, kind) { return Err(RuleError::InRiichi) }
+        let tiles = self.take_all_from_hand(seat, kind, 4).ok_or(RuleError::InvalidMeld(action))?;
+        self.melds[seat.index()].push(Meld { kind: MeldKind::ClosedKan, tiles, called: None, from: None });
+        self.reveal_dora();
+        self.flip_kan_dora(true);
+        self.actor = seat;
+        self.phase = Phase::Draw { seat, rinshan: true };
+        Ok(Event::Ankan { seat, tile })
+    }
+
+    // A closed kan in riichi must use the drawn tile and leave the waits as they were
+    fn keeps_waits(&self, seat: Wind, kind: usize) -> bool {
+        let hand = &self.hands[seat.index()];
+        let melds = &self.melds[seat.index()];
+        if hand.last().and_then(Tile::kind) != Some(kind) { return false }
+
+        let tiles_of = |waits: Vec<WaitingTile>| waits.into_iter().map(|wait| wait.tile).collect::<Vec<_>>();
+        let before = tiles_of(waits::waits(&hand[..hand.len() - 1], melds));
+
+        let (quad, rest): (Vec<Tile>, Vec<Tile>) = hand.iter().partition(|tile| tile.kind() == Some(kind));
+        let mut with_kan = melds.clone();
+        with_kan.push(Meld { kind: MeldKind::ClosedKan, tiles: quad, called: None, from: None });
+        before == tiles_of(waits::waits(&rest, &with_kan))
+    }
+
+    fn take_all_from_hand(&mut self, seat: Wind, kind: usize, count: usize) -> Option<Vec<Tile>> {
+        let hand = &mut self.hands[seat.index()];
+        if hand.iter().filter(|tile| tile.kind() == Some(kind)).count() != count { return None }
+
+        let (taken, kept) = hand.iter().partition(|tile| tile.kind() == Some(kind));
+        *hand = kept;
+        Some(taken)
+    }
+}
+
+// Walks a hand's action log, yielding the table after every stored byte and stopping at the first illegal one
+pub struct Replay<'a> {
+    state: GameState,
+    actions: std::slice::Iter<'a, u8>,
+    failed: bool,
+}
+
+impl<'a> Replay<'a> {
+    pub(crate) fn new(init: &InitialState, ruleset: Ruleset, actions: &'a [u8]) -> Self {
+        Self {
+            state: GameState::new_with_ruleset(init, ruleset),
+            actions: actions.iter(),
+            failed: false,
+        }
+    }
+}
+
+impl Replay<'_> {
+    fn advance(&mut self) -> Option<Result<Event, RuleError>> {
+        if self.failed { return None }
+
+        let raw = self.actions.next()?;
+        let result = self.state.step(*raw);
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Result<GameState, RuleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance().map(|result| result.map(|_| self.state.clone()))
+    }
+}
+
+// The same walk as Replay, yielding what each stored byte meant instead of the table after it
+pub struct Events<'a>(Replay<'a>);
+
+impl<'a> Events<'a> {
+    pub(crate) fn new(init: &InitialState, ruleset: Ruleset, actions: &'a [u8]) -> Self {
+        Self(Replay::new(init, ruleset, actions))
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<Event, RuleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.advance()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::replay::*;
+    use crate::test::scripted_init;
+    use crate::{Hand, Action};
+    use crate::tiles::Tile::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<GameState>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<GameState>();
+    }
+
+    #[test]
+    fn snapshot_every_step() {
+        let init = scripted_init([&[], &[], &[], &[]], &[]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+        for tile in &init.living_wall[..8] {
+            hand.draw(*tile).unwrap().discard(*tile).unwrap();
+        }
+
+        let states: Vec<GameState> = hand.replay().map(Result::unwrap).collect();
+        assert_eq!(states.len(), 16);
+        for (step, state) in states.iter().enumerate() {
+            let seat = Wind::try_from((step / 2 % 4) as u8).unwrap();
+            assert_eq!(state.actor(), seat);
+            assert_eq!(state.hand(seat).len(), if step % 2 == 0 { 14 } else { 13 });
+            assert_eq!(state.living_wall(), &init.living_wall[step / 2 + 1..]);
+            assert_eq!(state.dora_indicators(), &init.dead_wall[4..5]);
+        }
+
+        let last = states.last().unwrap();
+        assert_eq!(last.river(Wind::North).iter().map(|discard| discard.tile).collect::<Vec<_>>(), vec![init.living_wall[3], init.living_wall[7]]);
+        assert_eq!(*last, hand.state().unwrap());
+        assert!(!last.is_ended());
+    }
+
+    #[test]
+    fn kans_and_riichi() {
+        let east: &[Tile] = &[HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon];
+        let south: &[Tile] = &[HonorRedDragon];
+        let west: &[Tile] = &[HonorRedDragon, HonorRedDragon, HonorRedDragon];
+        let init = scripted_init([east, south, west, &[]], &[HonorWhiteDragon, ManOne, ManTwo, ManThree]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+
+        hand.draw(HonorWhiteDragon).unwrap()
+            .act(Action::CallChiiOrDeclareKan, Some(HonorWhiteDragon)).unwrap();
+        let state = hand.state().unwrap();
+        assert_eq!(state.dora_indicators(), &init.dead_wall[4..6]);
+        assert_eq!(state.melds(Wind::East)[0], Meld { kind: MeldKind::ClosedKan, tiles: vec![HonorWhiteDragon; 4], called: Option::None, from: Option::None });
+
+        hand.draw(init.dead_wall[0]).unwrap().discard(init.dead_wall[0]).unwrap()
+            .draw(ManOne).unwrap().discard(HonorRedDragon).unwrap()
+            .act(Action::CallKanByRight, Option::None).unwrap();
+        let state = hand.state().unwrap();
+        assert_eq!(state.actor(), Wind::West);
+        assert_eq!(state.dora_indicators().len(), 2);
+        assert!(state.river(Wind::South)[0].called);
+        assert_eq!(state.melds(Wind::West)[0].from, Some(Wind::South));
+
+        hand.draw(init.dead_wall[1]).unwrap().discard(init.dead_wall[1]).unwrap();
+        let state = hand.state().unwrap();
+        assert_eq!(state.dora_indicators(), &init.dead_wall[4..7]);
+        assert_eq!(state.dead_wall().ura_indicators(), &init.dead_wall[9..12]);
+        assert_eq!(state.dead_wall().dora()[2], init.dead_wall[6].dora_from_indicator());
+        // Each replacement draw shortens the living wall from the back
+        assert_eq!(state.living_wall(), &init.living_wall[2..init.living_wall.len() - 2]);
+
+        hand.draw(ManTwo).unwrap().act(Action::DeclareRiichi, Some(ManTwo)).unwrap();
+        let state = hand.state().unwrap();
+        assert!(state.river(Wind::North)[0].riichi);
+        assert!(!state.is_riichi(Wind::North));
+
+        hand.draw(ManThree).unwrap();
+        assert!(hand.state().unwrap().is_riichi(Wind::North));
+    }
+
+    #[test]
+    fn wins_count_yaku() {
+        use crate::{Yaku, Wait};
+        let east: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            SouOne, SouOne, SouTwo, SouThree,
+        ];
+        let init = scripted_init([east, &[], &[], &[]], &[HonorWest, SouFour]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(HonorWest).unwrap()
+            .act(Action::DeclareRiichi, Some(HonorWest)).unwrap()
+            .draw(SouFour).unwrap().discard(SouFour).unwrap()
+            .act(Action::CallRonByLeft, Option::None).unwrap();
+
+        // Tenpai on 1-4 sou at the discard before the win
+        let waits = hand.replay().nth(1).unwrap().unwrap().waits(Wind::East);
+        assert_eq!(waits.iter().map(|wait| wait.tile).collect::<Vec<_>>(), vec![SouOne, SouFour]);
+
+        let state = hand.state().unwrap();
+        let win = &state.wins()[0];
+        assert_eq!((win.seat, win.from, win.tile, win.wait), (Wind::East, Some(Wind::South), SouFour, Wait::Ryanmen));
+        assert_eq!(win.yaku, vec![Yaku::DoubleRiichi, Yaku::Ippatsu, Yaku::Pinfu, Yaku::Ittsu]);
+        assert_eq!(win.han(), 6);
+        assert_eq!(win.score().han, 6 + win.dora + win.red_fives + win.ura_dora);
+    }
+
+    #[test]
+    fn settles_points() {
+        let east: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            SouOne, SouOne, SouTwo, SouThree,
+        ];
+        let mut init = scripted_init([east, &[], &[], &[]], &[HonorWest, SouFour]);
+        init.hanba_count = 2;
+        init.unclaimed_riichi_count = 1;
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(HonorWest).unwrap().act(Action::DeclareRiichi, Some(HonorWest)).unwrap();
+        assert_eq!(hand.state().unwrap().score_deltas(), Option::None);
+        hand.draw(SouFour).unwrap().discard(SouFour).unwrap()
+            .act(Action::CallRonByLeft, Option::None).unwrap();
+
+        // The discarder pays both honba, and the dealer takes back their own stick plus the one on the table
+        let state = hand.state().unwrap();
+        let [east, south, west, north] = state.wins()[0].payments();
+        assert_eq!(state.score_deltas(), Some([east + 600 + 1000, south - 600, west, north]));
+    }
+
+    #[test]
+    fn wins_need_yaku() {
+        let south: &[Tile] = &[
+            PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight,
+            ManOne, ManOne, HonorWest, HonorWest, HonorNorth,
+        ];
+        let init = scripted_init([&[HonorWest], south, &[], &[]], &[ManOne, HonorNorth, ManNine]);
+        let mut hand = Hand::new_from_unchecked(init);
+
+        hand.draw(ManOne).unwrap().discard(HonorWest).unwrap()
+            .act(Action::CallPonByRight, Option::None).unwrap()
+            .discard(HonorNorth).unwrap()
+            .draw(HonorNorth).unwrap().discard(HonorNorth).unwrap()
+            .draw(ManNine).unwrap().discard(ManNine).unwrap();
+        assert_eq!(hand.act(Action::CallRonByOpposite, Option::None).err(), Some(RuleError::NoYaku));
+        assert_eq!(hand.state().unwrap().wins(), &[]);
+    }
+
+    #[test]
+    fn riichi_legality() {
+        let east: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            HonorEast, HonorEast, HonorEast, SouOne,
+        ];
+        let init = scripted_init([east, &[], &[], &[]], &[HonorWest, HonorWest, HonorWest, HonorGreenDragon, HonorEast]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+
+        hand.draw(HonorWest).unwrap();
+        assert_eq!(hand.act(Action::DeclareRiichi, Some(PinOne)).err(), Some(RuleError::NotTenpai));
+        assert_eq!(hand.act(Action::DeclareRiichi, Some(ManOne)).err(), Some(RuleError::TileNotHeld(ManOne)));
+        hand.act(Action::DeclareRiichi, Some(HonorWest)).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().discard(HonorGreenDragon).unwrap()
+            .draw(HonorEast).unwrap();
+        assert_eq!(hand.discard(PinOne).err(), Some(RuleError::InRiichi));
+        assert_eq!(hand.act(Action::DeclareRiichi, Some(HonorEast)).err(), Some(RuleError::InRiichi));
+
+        // A kan on the drawn tile that keeps the single wait on 1 sou is fine
+        hand.act(Action::CallChiiOrDeclareKan, Some(HonorEast)).unwrap()
+            .draw(init.dead_wall[0]).unwrap();
+        assert_eq!(hand.discard(SouOne).err(), Some(RuleError::InRiichi));
+        hand.discard(init.dead_wall[0]).unwrap();
+
+        // With 111 and 2 sou the kan would drop the wait on 3
+        let east: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            SouOne, SouOne, SouOne, SouTwo,
+        ];
+        let init = scripted_init([east, &[], &[], &[]], &[HonorWest, HonorWest, HonorWest, HonorGreenDragon, SouOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().act(Action::DeclareRiichi, Some(HonorWest)).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().discard(HonorGreenDragon).unwrap()
+            .draw(SouOne).unwrap();
+        assert_eq!(hand.act(Action::CallChiiOrDeclareKan, Some(SouOne)).err(), Some(RuleError::InRiichi));
+
+        let mut init = scripted_init([east, &[], &[], &[]], &[HonorWest, HonorWest, HonorWest, HonorWest]);
+        init.living_wall.truncate(4);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap();
+        assert_eq!(hand.act(Action::DeclareRiichi, Some(HonorWest)).err(), Some(RuleError::TooFewTiles));
+    }
+
+    #[test]
+    fn riichi_discards_drawn_copy() {
+        // South already holds a pair of the north wind it draws after riichi
+        let south: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine,
+            HonorNorth, HonorNorth, SouTwo, SouThree,
+        ];
+        let init = scripted_init([&[], south, &[], &[]], &[HonorWest, HonorGreenDragon, ManOne, ManOne, ManOne, HonorNorth]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().act(Action::DeclareRiichi, Some(HonorGreenDragon)).unwrap()
+            .draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(HonorNorth).unwrap()
+            .discard(HonorNorth).unwrap();
+        assert_eq!(hand.state().unwrap().river(Wind::South).last().map(|river| river.tile), Some(HonorNorth));
+    }
+
+    #[test]
+    fn replay_stops_at_error() {
+        let init = scripted_init([&[], &[], &[], &[]], &[ManOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw_unchecked(ManTwo).draw_unchecked(ManOne);
+
+        let steps: Vec<_> = hand.replay().collect();
+        assert_eq!(steps, vec![Err(RuleError::WrongDraw { expected: ManOne, found: ManTwo })]);
+    }
+}
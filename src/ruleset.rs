@@ -0,0 +1,241 @@
+use serde::{Serialize, Deserialize};
+use serde_repr::{Serialize_repr, Deserialize_repr};
+use num_enum::TryFromPrimitive;
+
+use crate::mode::Mode;
+use crate::tiles::Tile;
+use crate::validation::RedFives;
+
+// What happens when more than one player calls ron on the same tile
+#[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq, Clone, Copy, Default, TryFromPrimitive, Hash)]
+#[repr(u8)]
+pub enum MultipleRon {
+    // Two players may both win, and a third ron ends the hand as sancha hou
+    #[default]
+    Double = 0,
+    // Atamahane, where only the first ron recorded stands. Replays do not check that it came from the
+    // winner nearest the discarder, so logs must record that ron first.
+    Single = 1,
+    // All three may win
+    Triple = 2,
+}
+
+// When kan dora are flipped
+#[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq, Clone, Copy, Default, TryFromPrimitive, Hash)]
+#[repr(u8)]
+pub enum KanDora {
+    // Straight away for closed kans, and after the next discard for called and added ones
+    #[default]
+    Mixed = 0,
+    Immediate = 1,
+    // After the next discard for every kan
+    Delayed = 2,
+}
+
+// The table rules a hand or match is played under. The default is what hands recorded without one
+// have always replayed with, so only the differences are ever stored.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[serde(default)]
+pub struct Ruleset {
+    #[serde(rename="a", skip_serializing_if = "crate::is_default")]
+    pub red_fives: RedFives,
+    // Tanyao counts with an open hand
+    #[serde(rename="k", skip_serializing_if = "is_true")]
+    pub kuitan: bool,
+    #[serde(rename="r", skip_serializing_if = "crate::is_default")]
+    pub multiple_ron: MultipleRon,
+    // A win may rely on a yaku that only some of its waits complete
+    #[serde(rename="z", skip_serializing_if = "is_true")]
+    pub atozuke: bool,
+    // 4 han 30 fu and 3 han 60 fu are rounded up to mangan
+    #[serde(rename="g", skip_serializing_if = "crate::is_default")]
+    pub kiriage_mangan: bool,
+    // Riichi may be declared with the hand shown, with Action::DeclareOpenRiichi, for an extra han
+    #[serde(rename="o", skip_serializing_if = "crate::is_default")]
+    pub open_riichi: bool,
+    // A match ends as soon as any player falls below zero
+    #[serde(rename="b", skip_serializing_if = "crate::is_default")]
+    pub busting: bool,
+    #[serde(rename="d", skip_serializing_if = "crate::is_default")]
+    pub kan_dora: KanDora,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            red_fives: RedFives::default(),
+            kuitan: true,
+            multiple_ron: MultipleRon::default(),
+            atozuke: true,
+            kiriage_mangan: false,
+            open_riichi: false,
+            busting: false,
+            kan_dora: KanDora::default(),
+        }
+    }
+}
+
+impl Ruleset {
+    // The default rules for the mode, where sanma has no red man as it has no 5 man
+    pub fn for_mode(mode: Mode) -> Self {
+        let mut red_fives = RedFives::default();
+        if !mode.has_tile(Tile::ManRedFive) { red_fives.man = 0 }
+        Self { red_fives, ..Self::default() }
+    }
+}
+
+#[inline]
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ruleset::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::{self, *};
+    use crate::{Action, Event, Hand, RuleError, Wind, Yaku};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Ruleset>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Ruleset>();
+    }
+
+    #[test]
+    fn compact_encoding() {
+        let mut serial = vec![];
+        ciborium::into_writer(&Ruleset::default(), &mut serial).unwrap();
+        // An empty map
+        assert_eq!(serial, vec![0xa0]);
+
+        let rules = Ruleset { kuitan: false, multiple_ron: MultipleRon::Triple, red_fives: RedFives::NONE, ..Ruleset::default() };
+        let mut serial = vec![];
+        ciborium::into_writer(&rules, &mut serial).unwrap();
+        let output: Ruleset = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!(output, rules);
+
+        // Hands stored without a ruleset read back with the default
+        let hand = Hand::new_from_unchecked(scripted_init([&[], &[], &[], &[]], &[]));
+        let mut serial = vec![];
+        ciborium::into_writer(&hand, &mut serial).unwrap();
+        let map: ciborium::Value = ciborium::from_reader(&serial[..]).unwrap();
+        assert!(map.as_map().unwrap().iter().all(|(key, _)| key.as_text() != Some("r")));
+        let ruled = hand.clone().with_ruleset(rules);
+        let mut serial = vec![];
+        ciborium::into_writer(&ruled, &mut serial).unwrap();
+        let output: Hand = ciborium::from_reader(&serial[..]).unwrap();
+        assert_eq!(output.ruleset(), rules);
+    }
+
+    #[test]
+    fn kuitan_and_atozuke() {
+        // After calling a pon of 8 sou, South waits on the 1 and 4 pin, and only the 4 leaves tanyao
+        let south: &[Tile] = &[
+            PinTwo, PinThree, SouTwo, SouThree, SouFour, ManFour, ManFive, ManSix, ManSeven, ManSeven,
+            SouEight, SouEight, HonorWest,
+        ];
+        let init = scripted_init([&[SouEight, PinFour], south, &[], &[]], &[HonorGreenDragon, ManOne, ManOne, ManOne]);
+        let play = |rules: Ruleset| {
+            let mut hand = Hand::new_from_unchecked(init.clone()).with_ruleset(rules);
+            hand.draw(HonorGreenDragon).unwrap().discard(SouEight).unwrap()
+                .act(Action::CallPonByRight, Option::None).unwrap()
+                .discard(HonorWest).unwrap()
+                .draw(ManOne).unwrap().discard(ManOne).unwrap()
+                .draw(ManOne).unwrap().discard(ManOne).unwrap()
+                .draw(ManOne).unwrap().discard(PinFour).unwrap();
+            hand
+        };
+
+        let mut open = play(Ruleset::default());
+        open.act(Action::CallRonByRight, Option::None).unwrap();
+        assert_eq!(open.state().unwrap().wins()[0].yaku, vec![Yaku::Tanyao]);
+
+        let no_kuitan = Ruleset { kuitan: false, ..Ruleset::default() };
+        assert_eq!(play(no_kuitan).act(Action::CallRonByRight, Option::None).err(), Some(RuleError::NoYaku));
+        let no_atozuke = Ruleset { atozuke: false, ..Ruleset::default() };
+        assert_eq!(play(no_atozuke).act(Action::CallRonByRight, Option::None).err(), Some(RuleError::Atozuke));
+    }
+
+    #[test]
+    fn multiple_ron() {
+        let south = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine, HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon, ManNine];
+        let west = [SouOne, SouTwo, SouThree, SouFour, SouFive, SouSix, SouSeven, SouEight, SouNine, HonorGreenDragon, HonorGreenDragon, HonorGreenDragon, ManNine];
+        let north = [PinTwo, PinThree, PinFour, SouTwo, SouThree, SouFour, ManTwo, ManThree, ManFour, HonorRedDragon, HonorRedDragon, HonorRedDragon, ManNine];
+        let init = scripted_init([&[], &south, &west, &north], &[ManNine]);
+        let play = |multiple_ron: MultipleRon| {
+            let mut hand = Hand::new_from_unchecked(init.clone()).with_ruleset(Ruleset { multiple_ron, ..Ruleset::default() });
+            hand.draw(ManNine).unwrap().discard(ManNine).unwrap()
+                .act(Action::CallRonByRight, Option::None).unwrap();
+            hand
+        };
+
+        let mut single = play(MultipleRon::Single);
+        assert_eq!(single.act(Action::CallRonByOpposite, Option::None).err(), Some(RuleError::UnexpectedAction(Action::CallRonByOpposite)));
+        assert_eq!(single.state().unwrap().wins().len(), 1);
+
+        let mut triple = play(MultipleRon::Triple);
+        triple.act(Action::CallRonByOpposite, Option::None).unwrap()
+            .act(Action::CallRonByLeft, Option::None).unwrap();
+        let state = triple.state().unwrap();
+        assert_eq!(state.wins().len(), 3);
+        assert_eq!(state.abortive_draw(), Option::None);
+        assert_eq!(state.score_deltas().unwrap().iter().sum::<i32>(), 0);
+    }
+
+    #[test]
+    fn kan_dora_timing() {
+        let east = [PinOne, PinOne, PinOne, PinOne, SouOne];
+        let init = scripted_init([&east, &[], &[], &[]], &[HonorWest]);
+        let rinshan = init.dead_wall[0];
+        let closed_kan = |kan_dora: KanDora| {
+            let mut hand = Hand::new_from_unchecked(init.clone()).with_ruleset(Ruleset { kan_dora, ..Ruleset::default() });
+            hand.draw(HonorWest).unwrap().act(Action::CallChiiOrDeclareKan, Some(PinOne)).unwrap().draw(rinshan).unwrap();
+            hand
+        };
+        assert_eq!(closed_kan(KanDora::Mixed).state().unwrap().dora_indicators().len(), 2);
+        let mut delayed = closed_kan(KanDora::Delayed);
+        assert_eq!(delayed.state().unwrap().dora_indicators().len(), 1);
+        delayed.discard(rinshan).unwrap();
+        assert_eq!(delayed.state().unwrap().dora_indicators().len(), 2);
+
+        let init = scripted_init([&[SouOne], &[SouOne, SouOne, SouOne], &[], &[]], &[HonorWest]);
+        let called_kan = |kan_dora: KanDora| {
+            let mut hand = Hand::new_from_unchecked(init.clone()).with_ruleset(Ruleset { kan_dora, ..Ruleset::default() });
+            hand.draw(HonorWest).unwrap().discard(SouOne).unwrap().act(Action::CallKanByRight, Option::None).unwrap();
+            hand.state().unwrap().dora_indicators().len()
+        };
+        assert_eq!(called_kan(KanDora::Mixed), 1);
+        assert_eq!(called_kan(KanDora::Immediate), 2);
+    }
+
+    #[test]
+    fn open_riichi() {
+        let south: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine,
+            HonorNorth, HonorNorth, SouTwo, SouThree,
+        ];
+        let init = scripted_init([&[SouFour], south, &[], &[]], &[HonorWest, HonorGreenDragon, ManOne, ManOne, ManOne]);
+        let mut hand = Hand::new_from_unchecked(init.clone());
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap().draw(HonorGreenDragon).unwrap();
+        assert_eq!(hand.act(Action::DeclareOpenRiichi, Some(HonorGreenDragon)).err(), Some(RuleError::UnexpectedAction(Action::DeclareOpenRiichi)));
+
+        let mut hand = Hand::new_from_unchecked(init).with_ruleset(Ruleset { open_riichi: true, ..Ruleset::default() });
+        hand.draw(HonorWest).unwrap().discard(HonorWest).unwrap()
+            .draw(HonorGreenDragon).unwrap().act(Action::DeclareOpenRiichi, Some(HonorGreenDragon)).unwrap();
+        assert_eq!(hand.events().last(), Some(Ok(Event::Riichi { seat: Wind::South, discard: HonorGreenDragon, open: true })));
+        hand.draw(ManOne).unwrap().discard(ManOne).unwrap()
+            .draw(ManOne).unwrap().discard(ManOne).unwrap();
+        assert!(hand.state().unwrap().is_open_riichi(Wind::South));
+
+        hand.draw(ManOne).unwrap().discard(SouFour).unwrap()
+            .act(Action::CallRonByRight, Option::None).unwrap();
+        assert_eq!(hand.state().unwrap().wins()[0].yaku, vec![Yaku::DoubleRiichi, Yaku::OpenRiichi, Yaku::Ippatsu, Yaku::Pinfu]);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::mode::Mode;
+use crate::replay::RiverTile;
+use crate::Wind;
+
+// How a hand that ran out of tiles is settled, and what the next hand starts from
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ExhaustiveDraw {
+    // Only waits on a tile the player does not hold every copy of count, so karaten is noten
+    pub tenpai: [bool; 4],
+    // Every discard a terminal or honor, with none of them called
+    pub nagashi_mangan: [bool; 4],
+    // Noten payments, or mangan tsumo payments for each nagashi mangan instead, less any riichi stick just placed
+    pub deltas: [i32; 4],
+    // The dealer stays on when tenpai
+    pub dealer_keeps: bool,
+    pub repeat_count: u8,
+    pub hanba_count: u8,
+    pub unclaimed_riichi_count: u8,
+}
+
+impl ExhaustiveDraw {
+    pub(crate) fn new(mode: Mode, tenpai: [bool; 4], rivers: &[Vec<RiverTile>; 4], riichi: [bool; 4], counts: (u8, u8, u8)) -> Self {
+        let (repeat_count, hanba_count, riichi_sticks) = counts;
+        let nagashi_mangan = rivers.each_ref().map(|river| is_nagashi(river));
+
+        let mut deltas = if nagashi_mangan.contains(&true) {
+            nagashi_payments(mode, nagashi_mangan)
+        }
+        else {
+            noten_payments(mode, tenpai)
+        };
+        for (delta, _) in deltas.iter_mut().zip(riichi).filter(|(_, riichi)| *riichi) {
+            *delta -= 1000;
+        }
+
+        let dealer_keeps = tenpai[Wind::East.index()];
+        let declared = riichi.iter().filter(|&&riichi| riichi).count() as u8;
+        Self {
+            tenpai,
+            nagashi_mangan,
+            deltas,
+            dealer_keeps,
+            repeat_count: if dealer_keeps { repeat_count.saturating_add(1) } else { 0 },
+            hanba_count: hanba_count.saturating_add(1),
+            unclaimed_riichi_count: riichi_sticks.saturating_add(declared),
+        }
+    }
+}
+
+fn is_nagashi(river: &[RiverTile]) -> bool {
+    !river.is_empty() && river.iter().all(|discard| !discard.called && discard.tile.is_terminal_or_honor())
+}
+
+// 3000 points split between the seats that are tenpai and the seats that are not, or 2000 in sanma
+fn noten_payments(mode: Mode, tenpai: [bool; 4]) -> [i32; 4] {
+    let (seats, total) = match mode {
+        Mode::Yonma => (4, 3000),
+        Mode::Sanma => (3, 2000),
+    };
+    let count = tenpai.iter().filter(|&&tenpai| tenpai).count() as i32;
+    if count == 0 || count == seats { return [0; 4] }
+
+    let mut deltas = [0; 4];
+    for seat in mode.seats() {
+        deltas[seat.index()] = if tenpai[seat.index()] { total / count } else { -total / (seats - count) };
+    }
+    deltas
+}
+
+// Each nagashi mangan is paid like a mangan tsumo
+fn nagashi_payments(mode: Mode, nagashi: [bool; 4]) -> [i32; 4] {
+    let mut deltas = [0; 4];
+    for &seat in mode.seats() {
+        if !nagashi[seat.index()] { continue }
+        let mut payer = mode.next(seat);
+        while payer != seat {
+            let points = if seat == Wind::East || payer == Wind::East { 4000 } else { 2000 };
+            deltas[payer.index()] -= points;
+            deltas[seat.index()] += points;
+            payer = mode.next(payer);
+        }
+    }
+    deltas
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ryuukyoku::*;
+    use crate::test::scripted_init;
+    use crate::tiles::Tile::{self, *};
+    use crate::{Action, Hand};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<ExhaustiveDraw>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<ExhaustiveDraw>();
+    }
+
+    #[test]
+    fn splits_noten_payments() {
+        assert_eq!(noten_payments(Mode::Yonma, [true, false, false, false]), [3000, -1000, -1000, -1000]);
+        assert_eq!(noten_payments(Mode::Yonma, [false, true, true, false]), [-1500, 1500, 1500, -1500]);
+        assert_eq!(noten_payments(Mode::Yonma, [true, true, true, false]), [1000, 1000, 1000, -3000]);
+        assert_eq!(noten_payments(Mode::Yonma, [true; 4]), [0; 4]);
+        assert_eq!(nagashi_payments(Mode::Yonma, [false, true, false, false]), [-4000, 8000, -2000, -2000]);
+        assert_eq!(nagashi_payments(Mode::Yonma, [true, false, false, false]), [12000, -4000, -4000, -4000]);
+
+        assert_eq!(noten_payments(Mode::Sanma, [true, false, false, false]), [2000, -1000, -1000, 0]);
+        assert_eq!(noten_payments(Mode::Sanma, [true, false, true, false]), [1000, -2000, 1000, 0]);
+        assert_eq!(noten_payments(Mode::Sanma, [true, true, true, false]), [0; 4]);
+        assert_eq!(nagashi_payments(Mode::Sanma, [false, true, false, false]), [-4000, 6000, -2000, 0]);
+    }
+
+    #[test]
+    fn detects_nagashi() {
+        let river = |tiles: &[Tile], called: bool| -> Vec<RiverTile> {
+            tiles.iter().map(|&tile| RiverTile { tile, riichi: false, called }).collect()
+        };
+        assert!(is_nagashi(&river(&[PinOne, HonorEast, ManNine, HonorGreenDragon], false)));
+        assert!(!is_nagashi(&river(&[PinOne, HonorEast, ManEight], false)));
+        assert!(!is_nagashi(&river(&[PinOne, HonorEast], true)));
+        assert!(!is_nagashi(&[]));
+
+        let rivers = [river(&[SouNine], false), vec![], vec![], vec![]];
+        let draw = ExhaustiveDraw::new(Mode::Yonma, [false, true, false, false], &rivers, [false, true, false, false], (1, 0, 0));
+        assert_eq!(draw.nagashi_mangan, [true, false, false, false]);
+        assert_eq!(draw.deltas, [12000, -5000, -4000, -4000]);
+        assert!(!draw.dealer_keeps);
+        assert_eq!((draw.repeat_count, draw.hanba_count, draw.unclaimed_riichi_count), (0, 1, 1));
+    }
+
+    // Plays out the whole wall with every seat discarding what it draws
+    fn exhaust(hands: [&[Tile]; 4], wall_front: &[Tile]) -> Hand {
+        let mut init = scripted_init(hands, wall_front);
+        init.repeat_count = 2;
+        init.hanba_count = 2;
+        init.unclaimed_riichi_count = 1;
+        let mut hand = Hand::new_from_unchecked(init);
+        for tile in hand.initial_state().living_wall.clone() {
+            hand.draw(tile).unwrap().discard(tile).unwrap();
+        }
+        hand
+    }
+
+    #[test]
+    fn settles_exhausted_wall() {
+        let south: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, ManSeven, ManEight, ManNine,
+            HonorNorth, HonorNorth, SouTwo, SouThree,
+        ];
+        let east: &[Tile] = &[
+            PinOne, PinFour, PinSeven, SouTwo, SouFive, SouEight, ManThree, ManSix, ManNine,
+            HonorEast, HonorSouth, HonorWest, HonorRedDragon,
+        ];
+        let hand = exhaust([east, south, &[], &[]], &[]);
+        let state = hand.state().unwrap();
+        assert!(state.is_ended());
+        assert_eq!(state.abortive_draw(), Option::None);
+
+        let draw = state.exhaustive_draw().unwrap();
+        assert!(draw.tenpai[Wind::South.index()]);
+        assert_eq!(draw.nagashi_mangan, [false; 4]);
+        assert!(!draw.dealer_keeps);
+        assert_eq!(draw.repeat_count, 0);
+        assert_eq!(draw.hanba_count, 3);
+        assert_eq!(draw.unclaimed_riichi_count, 1);
+        assert_eq!(draw.deltas, noten_payments(Mode::Yonma, draw.tenpai));
+        assert_eq!(draw.deltas.iter().sum::<i32>(), 0);
+    }
+
+    #[test]
+    fn karaten_is_noten() {
+        // West can only win on a fifth west wind
+        let west: &[Tile] = &[
+            PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, PinSeven, PinEight, PinNine,
+            HonorWest, HonorWest, HonorWest, HonorWest,
+        ];
+        let hand = exhaust([&[], &[], west, &[]], &[]);
+        let state = hand.state().unwrap();
+        assert!(state.waits(Wind::West).iter().all(|wait| wait.karaten));
+        assert!(!state.waits(Wind::West).is_empty());
+
+        let draw = state.exhaustive_draw().unwrap();
+        assert!(!draw.tenpai[Wind::West.index()]);
+        assert_eq!(draw.deltas, noten_payments(Mode::Yonma, draw.tenpai));
+    }
+
+    #[test]
+    fn only_after_exhaustion() {
+        let init = scripted_init([&[], &[], &[], &[]], &[ManOne]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(ManOne).unwrap().discard(ManOne).unwrap();
+        assert_eq!(hand.state().unwrap().exhaustive_draw(), Option::None);
+
+        let east: &[Tile] = &[PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest, PinTwo, PinThree, PinFour, SouFive];
+        let init = scripted_init([east, &[], &[], &[]], &[HonorNorth]);
+        let mut hand = Hand::new_from_unchecked(init);
+        hand.draw(HonorNorth).unwrap().act(Action::DeclareMulligan, Option::None).unwrap();
+        assert_eq!(hand.state().unwrap().exhaustive_draw(), Option::None);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::agari::{Decomposition, SetKind, Wait};
+use crate::yaku::{Win, WinContext, Yaku};
+use crate::Wind;
+
+// The named point limits, from five han upwards
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Limit {
+    Mangan,
+    Haneman,
+    Baiman,
+    Sanbaiman,
+    // Thirteen or more han without a yakuman
+    KazoeYakuman,
+    Yakuman,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Score {
+    // Dora included, or 13 per yakuman
+    pub han: u8,
+    pub fu: u8,
+    pub limit: Option<Limit>,
+    // Every payment is a multiple of these, rounded up to the next 100
+    pub basic_points: u32,
+}
+
+impl Win {
+    // Dora never add to a yakuman
+    pub fn score(&self) -> Score {
+        if self.is_yakuman() {
+            let han = self.han();
+            return Score { han, fu: self.fu, limit: Some(Limit::Yakuman), basic_points: 8000 * (han / 13) as u32 }
+        }
+
+        let han = self.han() + self.dora + self.red_fives + self.ura_dora + self.kita;
+        let (limit, basic_points) = match han {
+            13.. => (Some(Limit::KazoeYakuman), 8000),
+            11..=12 => (Some(Limit::Sanbaiman), 6000),
+            8..=10 => (Some(Limit::Baiman), 4000),
+            6..=7 => (Some(Limit::Haneman), 3000),
+            _ => {
+                let basic_points = self.fu as u32 * (1 << (han + 2));
+                // Kiriage takes 1920 up to the 2000 of mangan
+                let kiriage = self.ruleset.kiriage_mangan && basic_points == 1920;
+                if han == 5 || basic_points > 2000 || kiriage { (Some(Limit::Mangan), 2000) } else { (None, basic_points) }
+            },
+        };
+        Score { han, fu: self.fu, limit, basic_points }
+    }
+
+    // What every seat pays or receives for this win alone, before honba and riichi sticks. A sanma
+    // tsumo is paid as if the north seat were there, and its share is simply lost.
+    pub fn payments(&self) -> [i32; 4] {
+        let basic_points = self.score().basic_points;
+        let pay = |multiple: u32| basic_points.saturating_mul(multiple).div_ceil(100) as i32 * 100;
+        let dealer = self.seat == Wind::East;
+
+        let mut deltas = [0; 4];
+        match self.from {
+            Some(discarder) => {
+                let points = pay(if dealer { 6 } else { 4 });
+                deltas[discarder.index()] -= points;
+                deltas[self.seat.index()] += points;
+            },
+            None => {
+                let mut payer = self.mode.next(self.seat);
+                while payer != self.seat {
+                    let points = pay(if dealer || payer == Wind::East { 2 } else { 1 });
+                    deltas[payer.index()] -= points;
+                    deltas[self.seat.index()] += points;
+                    payer = self.mode.next(payer);
+                }
+            },
+        }
+        deltas
+    }
+}
+
+// Fu for one reading of the hand, rounded up to the next 10 except for the flat 25 of seven pairs
+pub(crate) fn fu(decomposition: &Decomposition, wait: Wait, finished: Option<usize>, context: &WinContext, closed: bool, yaku: &[Yaku]) -> u8 {
+    let tsumo = context.from.is_none();
+    if yaku.contains(&Yaku::Pinfu) { return if tsumo { 20 } else { 30 } }
+
+    let mut fu: u8 = 20;
+    if closed && !tsumo { fu += 10 }
+    if tsumo { fu += 2 }
+
+    match decomposition {
+        Decomposition::Chiitoitsu { .. } => return 25,
+        Decomposition::Kokushi { .. } => {},
+        Decomposition::Standard { pair, sets } => {
+            if matches!(wait, Wait::Kanchan | Wait::Penchan | Wait::Tanki) { fu += 2 }
+
+            // Double wind pairs count for both winds
+            if pair.suit() == Some(crate::tiles::Suit::Dragon) { fu += 2 }
+            if *pair == context.seat.tile() { fu += 2 }
+            if *pair == context.prevailing_wind.tile() { fu += 2 }
+
+            for (index, set) in sets.iter().enumerate() {
+                if set.kind == SetKind::Sequence { continue }
+                let mut set_fu = 2;
+                if set.tile.is_terminal_or_honor() { set_fu *= 2 }
+                // A triplet completed by ron counts as open
+                let by_ron = !tsumo && finished == Some(index);
+                if !set.open && !by_ron { set_fu *= 2 }
+                if set.kind == SetKind::Quad { set_fu *= 4 }
+                fu += set_fu;
+            }
+        },
+    }
+
+    // An open hand with nothing but sequences still scores 30
+    let fu = fu.div_ceil(10) * 10;
+    if fu == 20 { 30 } else { fu }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scoring::*;
+    use crate::mode::Mode;
+    use crate::replay::{Meld, MeldKind};
+    use crate::ruleset::Ruleset;
+    use crate::tiles::Tile::{self, *};
+    use crate::yaku::evaluate;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Score>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Score>();
+    }
+
+    fn context(seat: Wind, tile: Tile, from: Option<Wind>) -> WinContext {
+        WinContext {
+            seat,
+            prevailing_wind: Wind::East,
+            tile,
+            from,
+            riichi: false,
+            double_riichi: false,
+            open_riichi: false,
+            ippatsu: false,
+            last_tile: false,
+            rinshan: false,
+            chankan: false,
+            first_draw: false,
+            dora: 0,
+            red_fives: 0,
+            ura_dora: 0,
+            kita: 0,
+            mode: Mode::Yonma,
+            ruleset: Ruleset::default(),
+        }
+    }
+
+    #[test]
+    fn counts_fu() {
+        let pinfu = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, SouTwo, SouThree, SouFour, ManSix, ManSeven, ManEight, ManNine, ManNine];
+        let ron = evaluate(&pinfu, &[], &context(Wind::South, ManSix, Some(Wind::West))).unwrap();
+        assert_eq!(ron.score(), Score { han: 1, fu: 30, limit: Option::None, basic_points: 240 });
+        assert_eq!(ron.payments(), [0, 1000, -1000, 0]);
+        let tsumo = evaluate(&pinfu, &[], &context(Wind::South, ManSix, Option::None)).unwrap();
+        assert_eq!(tsumo.score(), Score { han: 2, fu: 20, limit: Option::None, basic_points: 320 });
+        assert_eq!(tsumo.payments(), [-700, 1500, -400, -400]);
+
+        // 20 base, a closed terminal triplet 8, an honor pon 4 and a kanchan 2
+        let hand = [PinOne, PinOne, PinOne, SouTwo, SouFour, SouThree, ManFive, ManSix, ManSeven, ManTwo, ManTwo];
+        let pon = Meld { kind: MeldKind::Pon, tiles: vec![HonorRedDragon; 3], called: Some(HonorRedDragon), from: Some(Wind::North) };
+        let win = evaluate(&hand, &[pon], &context(Wind::East, SouThree, Some(Wind::North))).unwrap();
+        assert_eq!(win.score(), Score { han: 1, fu: 40, limit: Option::None, basic_points: 320 });
+        assert_eq!(win.payments(), [2000, 0, 0, -2000]);
+
+        let pairs = [PinOne, PinOne, PinNine, PinNine, SouTwo, SouTwo, SouThree, SouThree, HonorEast, HonorEast, HonorNorth, HonorNorth, HonorRedDragon, HonorRedDragon];
+        let win = evaluate(&pairs, &[], &context(Wind::West, PinOne, Option::None)).unwrap();
+        assert_eq!(win.score(), Score { han: 3, fu: 25, limit: Option::None, basic_points: 800 });
+        assert_eq!(win.payments(), [-1600, -800, 3200, -800]);
+    }
+
+    #[test]
+    fn counts_limits() {
+        let chinitsu = [ManOne, ManTwo, ManThree, ManTwo, ManThree, ManFour, ManFour, ManFive, ManSix, ManSeven, ManEight, ManNine, ManNine, ManNine];
+        let mut with_dora = context(Wind::North, ManNine, Some(Wind::East));
+        with_dora.dora = 1;
+        let win = evaluate(&chinitsu, &[], &with_dora).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::Pinfu, Yaku::Ittsu, Yaku::Chinitsu]);
+        assert_eq!(win.score().limit, Some(Limit::Baiman));
+        assert_eq!(win.payments(), [-16000, 0, 0, 16000]);
+
+        // Four han past 2000 basic points is capped at mangan
+        let hand = [PinTwo, PinThree, PinFour, SouSix, SouSeven, SouEight, ManTwo, ManTwo, ManTwo, HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon, ManFive, ManRedFive];
+        let mut dora = context(Wind::South, ManFive, Some(Wind::West));
+        dora.dora = 2;
+        dora.red_fives = 1;
+        let win = evaluate(&hand, &[], &dora).unwrap();
+        assert_eq!(win.score(), Score { han: 4, fu: 50, limit: Some(Limit::Mangan), basic_points: 2000 });
+
+        // 4 han 30 fu is 1920 basic points, unless kiriage rounds it up
+        let pinfu = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, SouTwo, SouThree, SouFour, ManSix, ManSeven, ManEight, ManNine, ManNine];
+        let mut three_dora = context(Wind::South, ManSix, Some(Wind::West));
+        three_dora.dora = 3;
+        let win = evaluate(&pinfu, &[], &three_dora).unwrap();
+        assert_eq!(win.score(), Score { han: 4, fu: 30, limit: Option::None, basic_points: 1920 });
+        three_dora.ruleset.kiriage_mangan = true;
+        let win = evaluate(&pinfu, &[], &three_dora).unwrap();
+        assert_eq!(win.score(), Score { han: 4, fu: 30, limit: Some(Limit::Mangan), basic_points: 2000 });
+
+        let orphans = [PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest, HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorGreenDragon, HonorGreenDragon];
+        let win = evaluate(&orphans, &[], &context(Wind::East, HonorRedDragon, Option::None)).unwrap();
+        assert_eq!(win.score().limit, Some(Limit::Yakuman));
+        assert_eq!(win.payments(), [48000, -16000, -16000, -16000]);
+
+        let mut sanma = context(Wind::South, HonorRedDragon, Option::None);
+        sanma.mode = Mode::Sanma;
+        sanma.kita = 2;
+        let win = evaluate(&orphans, &[], &sanma).unwrap();
+        assert_eq!(win.payments(), [-16000, 24000, -8000, 0]);
+        // Kita add to the han like any other dora
+        let mut kita = context(Wind::South, SouSix, Option::None);
+        kita.mode = Mode::Sanma;
+        kita.kita = 3;
+        let pinfu = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, SouTwo, SouThree, SouFour, SouSix, SouSeven, SouEight, PinNine, PinNine];
+        let win = evaluate(&pinfu, &[], &kita).unwrap();
+        assert_eq!(win.score(), Score { han: 5, fu: 20, limit: Some(Limit::Mangan), basic_points: 2000 });
+        assert_eq!(win.payments(), [-4000, 6000, -2000, 0]);
+    }
+}
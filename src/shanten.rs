@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::agari::{self, ORPHANS};
+use crate::tiles::Tile;
+
+// Shanten counts the tile swaps a hand needs to reach tenpai, so tenpai is 0 and a complete hand is -1.
+// Fixed melds are called or declared sets that no longer sit in the concealed tiles, and red
+// fives count as plain fives throughout.
+pub fn shanten(concealed: &[Tile], fixed_melds: usize) -> i8 {
+    let standard = standard_shanten(concealed, fixed_melds);
+    [chiitoitsu_shanten(concealed, fixed_melds), kokushi_shanten(concealed, fixed_melds)]
+        .into_iter()
+        .flatten()
+        .fold(standard, i8::min)
+}
+
+// Four sets and a pair
+pub fn standard_shanten(concealed: &[Tile], fixed_melds: usize) -> i8 {
+    let mut counts = agari::to_counts(concealed);
+    let mut cache = HashMap::new();
+    let mut best = i8::MAX;
+
+    for pair in 0..34 {
+        if counts[pair] < 2 { continue }
+        counts[pair] -= 2;
+        best = best.min(best_without_pair(&counts, fixed_melds as i8, &mut cache) - 1);
+        counts[pair] += 2;
+    }
+
+    best.min(best_without_pair(&counts, fixed_melds as i8, &mut cache))
+}
+
+// Seven distinct pairs, only possible without any melds
+pub fn chiitoitsu_shanten(concealed: &[Tile], fixed_melds: usize) -> Option<i8> {
+    if fixed_melds > 0 { return None }
+
+    let counts = agari::to_counts(concealed);
+    let pairs = counts.iter().filter(|&&count| count >= 2).count() as i8;
+    let kinds = counts.iter().filter(|&&count| count > 0).count() as i8;
+    Some(6 - pairs + (7 - kinds).max(0))
+}
+
+// One of every terminal and honor plus a pair of any of them, only possible without any melds
+pub fn kokushi_shanten(concealed: &[Tile], fixed_melds: usize) -> Option<i8> {
+    if fixed_melds > 0 { return None }
+
+    let counts = agari::to_counts(concealed);
+    let kinds = ORPHANS.iter().filter(|&&kind| counts[kind] > 0).count() as i8;
+    let has_pair = ORPHANS.iter().any(|&kind| counts[kind] >= 2);
+    Some(13 - kinds - has_pair as i8)
+}
+
+type SuitCache = HashMap<[u8; 9], Vec<(i8, i8)>>;
+
+// Suits never share a set, so each is broken into blocks on its own and the results combined
+fn best_without_pair(counts: &[u8; 34], sets: i8, cache: &mut SuitCache) -> i8 {
+    let mut combined = vec![(sets, 0i8)];
+    for suit in 0..3 {
+        let options = suit_blocks(counts[suit * 9..suit * 9 + 9].try_into().unwrap(), cache);
+        combined = combined.iter()
+            .flat_map(|&(sets, partials)| options.iter().map(move |&(more_sets, more_partials)| (sets + more_sets, partials + more_partials)))
+            .collect();
+        combined.sort_unstable();
+        combined.dedup();
+    }
+
+    let honor_sets = counts[27..].iter().filter(|&&count| count >= 3).count() as i8;
+    let honor_partials = counts[27..].iter().filter(|&&count| count == 2).count() as i8;
+    combined.into_iter()
+        .map(|(sets, partials)| {
+            let sets = sets + honor_sets;
+            // Only four blocks count towards the hand beyond the pair
+            let partials = (partials + honor_partials).min(4 - sets).max(0);
+            8 - 2 * sets - partials
+        })
+        .min()
+        .unwrap()
+}
+
+// Every undominated count of sets and partial sets a single suit can be broken into, trying each
+// way to use its lowest remaining tile as part of a set, a partial set, or left on its own.
+fn suit_blocks(counts: [u8; 9], cache: &mut SuitCache) -> Vec<(i8, i8)> {
+    let Some(kind) = (0..9).find(|&kind| counts[kind] > 0) else { return vec![(0, 0)] };
+    if let Some(found) = cache.get(&counts) { return found.clone() }
+
+    let mut found = vec![];
+    let mut explore = |used: &[usize], sets: i8, partials: i8, cache: &mut SuitCache| {
+        let mut rest = counts;
+        used.iter().for_each(|&kind| rest[kind] -= 1);
+        found.extend(suit_blocks(rest, cache).into_iter().map(|(more_sets, more_partials)| (sets + more_sets, partials + more_partials)));
+    };
+
+    if counts[kind] >= 3 { explore(&[kind, kind, kind], 1, 0, cache) }
+    if kind < 7 && counts[kind + 1] > 0 && counts[kind + 2] > 0 { explore(&[kind, kind + 1, kind + 2], 1, 0, cache) }
+    if counts[kind] >= 2 { explore(&[kind, kind], 0, 1, cache) }
+    if kind < 8 && counts[kind + 1] > 0 { explore(&[kind, kind + 1], 0, 1, cache) }
+    if kind < 7 && counts[kind + 2] > 0 { explore(&[kind, kind + 2], 0, 1, cache) }
+    // Leave this copy isolated
+    explore(&[kind], 0, 0, cache);
+
+    found.sort_unstable();
+    found.dedup();
+    let undominated: Vec<(i8, i8)> = found.iter()
+        .filter(|&&(sets, partials)| !found.iter().any(|&(other_sets, other_partials)| {
+            (other_sets, other_partials) != (sets, partials) && other_sets >= sets && other_partials >= partials
+        }))
+        .copied()
+        .collect();
+    cache.insert(counts, undominated.clone());
+    undominated
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shanten::*;
+    use crate::tiles::Tile::*;
+
+    #[test]
+    fn validate_standard() {
+        // Complete
+        assert_eq!(shanten(&[
+            PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine,
+            HonorEast, HonorEast, HonorEast, HonorRedDragon, HonorRedDragon,
+        ], 0), -1);
+        // Tenpai on a kanchan
+        assert_eq!(shanten(&[
+            PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManNine,
+            HonorEast, HonorEast, HonorEast, HonorRedDragon, HonorRedDragon,
+        ], 0), 0);
+        // Iishanten
+        assert_eq!(shanten(&[
+            PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManNine,
+            HonorEast, HonorEast, HonorSouth, HonorRedDragon, HonorRedDragon,
+        ], 0), 1);
+        // Nothing connects
+        assert_eq!(shanten(&[
+            PinOne, PinFour, PinSeven, SouTwo, SouFive, SouEight, ManThree, ManSix, ManNine,
+            HonorEast, HonorSouth, HonorWest, HonorNorth,
+        ], 0), 6);
+    }
+
+    #[test]
+    fn validate_with_melds() {
+        assert_eq!(standard_shanten(&[SouOne], 4), 0);
+        assert_eq!(standard_shanten(&[SouOne, SouOne], 4), -1);
+        assert_eq!(standard_shanten(&[SouOne, SouTwo, ManFive, ManRedFive], 3), 0);
+        assert_eq!(standard_shanten(&[SouOne, SouFour, ManFive, HonorWest], 3), 2);
+        assert_eq!(chiitoitsu_shanten(&[SouOne], 4), Option::None);
+        assert_eq!(kokushi_shanten(&[SouOne], 4), Option::None);
+    }
+
+    #[test]
+    fn validate_red_fives() {
+        let plain = [PinFour, PinFive, PinSix, SouFive, SouFive, ManThree, ManFour, ManFive, ManSix, ManSeven, ManEight, HonorNorth, HonorNorth];
+        let red = [PinFour, PinRedFive, PinSix, SouFive, SouRedFive, ManThree, ManFour, ManRedFive, ManSix, ManSeven, ManEight, HonorNorth, HonorNorth];
+        assert_eq!(shanten(&plain, 0), 0);
+        assert_eq!(shanten(&red, 0), shanten(&plain, 0));
+    }
+
+    #[test]
+    fn validate_special() {
+        let pairs = [PinOne, PinOne, SouTwo, SouTwo, ManThree, ManThree, ManFive, ManRedFive, HonorEast, HonorEast, HonorNorth, HonorNorth, HonorWhiteDragon];
+        assert_eq!(chiitoitsu_shanten(&pairs, 0), Some(0));
+        assert_eq!(shanten(&pairs, 0), 0);
+
+        // Four of a kind only makes a single pair
+        let quad = [PinOne, PinOne, PinOne, PinOne, ManThree, ManThree, ManFive, ManFive, HonorEast, HonorEast, HonorNorth, HonorNorth, HonorWhiteDragon];
+        assert_eq!(chiitoitsu_shanten(&quad, 0), Some(2));
+
+        let orphans = [PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest, HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorWhiteDragon];
+        assert_eq!(kokushi_shanten(&orphans, 0), Some(0));
+        assert_eq!(shanten(&orphans, 0), 0);
+        let mut complete = orphans.to_vec();
+        complete.push(HonorGreenDragon);
+        assert_eq!(shanten(&complete, 0), -1);
+    }
+
+    #[test]
+    fn agrees_with_agari() {
+        use crate::test::get_tiles;
+        use rand::prelude::SliceRandom;
+        use rand::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(6);
+        let mut tiles = get_tiles();
+        // Restrict to one suit and a few honors so complete hands actually turn up
+        tiles.retain(|tile| tile.suit() == Some(crate::tiles::Suit::Pin) || *tile == HonorEast);
+        for _ in 0..500 {
+            tiles.shuffle(&mut rng);
+            let hand = &tiles[..14];
+            assert_eq!(shanten(hand, 0) == -1, agari::is_complete(hand, 0), "{hand:?}");
+            assert!(shanten(&hand[..13], 0) >= 0);
+        }
+    }
+}
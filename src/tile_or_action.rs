@@ -1,6 +1,35 @@
+use std::fmt;
+
 use crate::actions::*;
 use crate::tiles::Tile;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum DecodeError {
+    // The tile bits hold Tile::None or a value past the last tile
+    InvalidTile(u8),
+    // The action bits name no defined action
+    InvalidAction(u8),
+}
+
+impl DecodeError {
+    pub fn byte(&self) -> u8 {
+        match self {
+            DecodeError::InvalidTile(raw) | DecodeError::InvalidAction(raw) => *raw,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidTile(raw) => write!(f, "byte {raw:#010b} does not hold a valid tile"),
+            DecodeError::InvalidAction(raw) => write!(f, "byte {raw:#010b} does not hold a valid action"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct TileOrAction {
     inner: u8
@@ -24,7 +53,52 @@ impl TileOrAction {
         }
     }
 
-    pub fn to_value_unchecked(&self, is_call: bool) -> (Action, Tile) {
+    // Accepts any byte that decodes as either a call or a declaration
+    pub fn new(value: u8) -> Result<Self, DecodeError> {
+        let item = Self::new_unchecked(value);
+        match item.decode(false) {
+            Ok(_) => Ok(item),
+            Err(error) => item.decode(true).map(|_| item).map_err(|_| error),
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.inner
+    }
+
+    // The checked form of to_value_unchecked, safe to run on untrusted bytes
+    pub fn decode(self, is_call: bool) -> Result<(Action, Tile), DecodeError> {
+        let tile = |raw: u8| match Tile::try_from(raw) {
+            Ok(Tile::None) | Err(_) => Err(DecodeError::InvalidTile(self.inner)),
+            Ok(tile) => Ok(tile),
+        };
+
+        if self.is_tile() {
+            return Ok((Action::None, tile(self.inner)?))
+        }
+
+        let with_tile = Action::has_tile(self.inner);
+        match (with_tile, is_call) {
+            // Only chii can be called with a tile, and never on an honor
+            (true, true) => {
+                if self.inner >> 6 != 0b01 { return Err(DecodeError::InvalidAction(self.inner)) }
+                let lowest = tile(self.inner & 0b1_1111)?;
+                if lowest.is_honor() { return Err(DecodeError::InvalidTile(self.inner)) }
+                Ok((Action::try_from(self.inner & 0b0110_0000).unwrap(), lowest))
+            },
+            (true, false) => {
+                Ok((Action::try_from(self.inner & 0b1100_0000).unwrap(), tile(self.inner & 0b0011_1111)?))
+            },
+            (false, _) => {
+                match Action::try_from(self.inner) {
+                    Ok(Action::None) | Err(_) => Err(DecodeError::InvalidAction(self.inner)),
+                    Ok(action) => Ok((action, Tile::None)),
+                }
+            },
+        }
+    }
+
+    pub fn to_value_unchecked(self, is_call: bool) -> (Action, Tile) {
         // There are a large number of commands that start with 11
         // Chii starts with 01, but can start with 011 for red fives
         if self.is_tile() { 
@@ -35,21 +109,21 @@ impl TileOrAction {
         match (with_tile, is_call) {
             // Call with tile
             (true, true) => {
-                return (
+                (
                     Action::try_from(self.inner & 0b0110_0000).unwrap(), 
                     Tile::try_from(self.inner & 0b1_1111).unwrap()
                 )
             },
             // Declare with tile
             (true, false) => {
-                return (
+                (
                     Action::try_from(self.inner & 0b1100_0000).unwrap(),
                     Tile::try_from(self.inner & 0b0011_1111).unwrap()
                 )
             },
             // Tileless call
             (false, _) => {
-                return (
+                (
                     Action::try_from(self.inner).unwrap(),
                     Tile::None
                 )
@@ -60,7 +134,7 @@ impl TileOrAction {
 
 #[cfg(test)]
 mod test {
-    use crate::tile_or_action::{TileOrAction};
+    use crate::tile_or_action::{DecodeError, TileOrAction};
     use crate::actions::*;
     use crate::tiles::*;
 
@@ -85,6 +159,8 @@ mod test {
 
         check_value(Action::DeclareRiichi, Tile::HonorSouth, false);
         check_value(Action::DeclareRiichi, Tile::SouNine, false);
+        check_value(Action::DeclareOpenRiichi, Tile::PinOne, false);
+        check_value(Action::DeclareOpenRiichi, Tile::HonorGreenDragon, false);
 
         check_value(Action::CallPonByLeft, Tile::None, true);
         check_value(Action::CallPonByOppositeWithRedFive, Tile::None, true);
@@ -109,5 +185,42 @@ mod test {
         let (out_action, out_tile) = combo.to_value_unchecked(is_call);
         assert_eq!(action, out_action);        
         assert_eq!(tile, out_tile);   
+        assert_eq!(combo.decode(is_call), Ok((action, tile)));
+        assert_eq!(TileOrAction::new(combo.value()), Ok(combo));
+    }
+
+    #[test]
+    fn reject_invalid() {
+        for raw in 38..64 {
+            check_invalid(raw, false, DecodeError::InvalidTile(raw));
+            check_invalid(raw, true, DecodeError::InvalidTile(raw));
+        }
+        check_invalid(0, false, DecodeError::InvalidTile(0));
+
+        // Undefined actions with 11 high bits
+        for raw in [0b1110_1011, 0b1111_0111, 0b1111_1011, 0b1111_1111] {
+            check_invalid(raw, false, DecodeError::InvalidAction(raw));
+            check_invalid(raw, true, DecodeError::InvalidAction(raw));
+        }
+
+        // Riichi and declared kans past the last tile, and chii or either riichi without one
+        check_invalid(0b1010_0110, false, DecodeError::InvalidTile(0b1010_0110));
+        check_invalid(0b0111_1111, false, DecodeError::InvalidTile(0b0111_1111));
+        check_invalid(0b1000_0000, false, DecodeError::InvalidTile(0b1000_0000));
+        check_invalid(0b1100_0000, false, DecodeError::InvalidTile(0b1100_0000));
+        check_invalid(0b1110_0110, false, DecodeError::InvalidTile(0b1110_0110));
+        assert_eq!(TileOrAction::new_unchecked(0b1100_0001).decode(true), Err(DecodeError::InvalidAction(0b1100_0001)));
+        assert_eq!(TileOrAction::new_unchecked(0b0100_0000).decode(true), Err(DecodeError::InvalidTile(0b0100_0000)));
+        assert_eq!(TileOrAction::new_unchecked(0b1000_0001).decode(true), Err(DecodeError::InvalidAction(0b1000_0001)));
+
+        // Red chii bytes that only make sense as calls are still well formed
+        let red_chii = TileOrAction::new(Action::CallChiiWithRedFive as u8 | Tile::ManThree as u8).unwrap();
+        assert_eq!(red_chii.decode(true), Ok((Action::CallChiiWithRedFive, Tile::ManThree)));
+    }
+
+    fn check_invalid(raw: u8, is_call: bool, expected: DecodeError) {
+        let item = TileOrAction::new_unchecked(raw);
+        assert_eq!(item.decode(is_call), Err(expected));
+        assert_eq!(TileOrAction::new(raw).err().map(|error| error.byte()), Some(raw));
     }
 }
\ No newline at end of file
@@ -94,17 +94,84 @@ impl Tile {
         })
     }
 
+    // The face value of a suited tile
+    pub fn number(&self) -> Option<u8> {
+        let kind = self.kind()?;
+        if kind >= 27 { return None }
+        Some((kind % 9) as u8 + 1)
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.number(), Some(1) | Some(9))
+    }
+
+    pub fn is_terminal_or_honor(&self) -> bool {
+        self.is_terminal() || self.is_honor()
+    }
+
+    // The tile a dora indicator points to, wrapping 9 to 1 and cycling the winds and the dragons
+    pub fn dora_from_indicator(&self) -> Tile {
+        let Some(kind) = self.kind() else { return Tile::None };
+        let next = match kind {
+            0..27 => kind / 9 * 9 + (kind % 9 + 1) % 9,
+            27..31 => 27 + (kind - 26) % 4,
+            // Red dragon, white dragon and green dragon in tile order, which wraps the same way
+            _ => 31 + (kind - 30) % 3,
+        };
+        Tile::from_kind(next)
+    }
+
     pub(crate) fn is_five(&self) -> bool {
-        match self {
+        matches!(self,
             Tile::ManFive |
             Tile::ManRedFive |
             Tile::PinFive |
             Tile::PinRedFive |
             Tile::SouFive |
-            Tile::SouRedFive => true,
-            _ => false
+            Tile::SouRedFive
+        )
+    }
+
+    pub fn is_red_five(&self) -> bool {
+        matches!(self, Tile::ManRedFive | Tile::PinRedFive | Tile::SouRedFive)
+    }
+
+    // Red fives play exactly like their plain counterparts outside of scoring
+    pub fn normalized(&self) -> Tile {
+        match self {
+            Tile::PinRedFive => Tile::PinFive,
+            Tile::SouRedFive => Tile::SouFive,
+            Tile::ManRedFive => Tile::ManFive,
+            _ => *self,
+        }
+    }
+
+    // Dense index over the 34 distinct tile kinds, with red fives folded into their five.
+    // Suited tiles are 0..27 in pin, sou, man order, followed by the seven honors.
+    pub(crate) fn kind(&self) -> Option<usize> {
+        let raw = self.normalized() as u8;
+        match self.suit()? {
+            Suit::Pin | Suit::Sou | Suit::Man => {
+                let suit_base = (raw - 1) / 10;
+                let offset = (raw - 1) % 10;
+                // Skip the red five slot that sits between five and six
+                let offset = if offset > 5 { offset - 1 } else { offset };
+                Some((suit_base * 9 + offset) as usize)
+            },
+            Suit::Wind | Suit::Dragon => Some((raw - Tile::HonorEast as u8) as usize + 27),
         }
     }
+
+    // The plain tile for a kind index, or Tile::None past the last kind
+    pub(crate) fn from_kind(kind: usize) -> Tile {
+        if kind >= 34 { return Tile::None }
+        if kind >= 27 { return Tile::try_from(Tile::HonorEast as u8 + (kind - 27) as u8).unwrap() }
+
+        let suit_base = (kind / 9) as u8 * 10;
+        let offset = (kind % 9) as u8;
+        let offset = if offset >= 5 { offset + 1 } else { offset };
+        Tile::try_from(suit_base + offset + 1).unwrap()
+    }
 }
 
 impl PartialOrd for Tile {
@@ -186,6 +253,51 @@ mod test {
         check_expect_suit(Tile::SouNine, Some(Suit::Sou), false);
     }
 
+    #[test]
+    fn validate_kind() {
+        let mut seen = vec![];
+        for raw in 1..=Tile::HonorGreenDragon as u8 {
+            let tile = Tile::try_from(raw).unwrap();
+            let kind = tile.kind().unwrap();
+            assert_eq!(Tile::from_kind(kind), tile.normalized());
+            if !tile.is_red_five() { seen.push(kind) }
+        }
+        assert_eq!(seen, (0..34).collect::<Vec<_>>());
+        assert_eq!(Tile::None.kind(), None);
+        assert_eq!(Tile::from_kind(34), Tile::None);
+        assert_eq!(Tile::PinRedFive.kind(), Tile::PinFive.kind());
+        assert_eq!(Tile::ManRedFive.normalized(), Tile::ManFive);
+        assert!(Tile::SouRedFive.is_red_five());
+        assert!(!Tile::SouFive.is_red_five());
+    }
+
+    #[test]
+    fn validate_dora() {
+        assert_eq!(Tile::PinOne.dora_from_indicator(), Tile::PinTwo);
+        assert_eq!(Tile::PinNine.dora_from_indicator(), Tile::PinOne);
+        assert_eq!(Tile::SouFour.dora_from_indicator(), Tile::SouFive);
+        assert_eq!(Tile::ManRedFive.dora_from_indicator(), Tile::ManSix);
+        assert_eq!(Tile::HonorNorth.dora_from_indicator(), Tile::HonorEast);
+        assert_eq!(Tile::HonorWest.dora_from_indicator(), Tile::HonorNorth);
+        assert_eq!(Tile::HonorWhiteDragon.dora_from_indicator(), Tile::HonorGreenDragon);
+        assert_eq!(Tile::HonorGreenDragon.dora_from_indicator(), Tile::HonorRedDragon);
+        assert_eq!(Tile::HonorRedDragon.dora_from_indicator(), Tile::HonorWhiteDragon);
+        assert_eq!(Tile::None.dora_from_indicator(), Tile::None);
+    }
+
+    #[test]
+    fn validate_number() {
+        assert_eq!(Tile::PinOne.number(), Some(1));
+        assert_eq!(Tile::SouRedFive.number(), Some(5));
+        assert_eq!(Tile::ManNine.number(), Some(9));
+        assert_eq!(Tile::HonorEast.number(), None);
+        assert_eq!(Tile::None.number(), None);
+        assert!(Tile::ManNine.is_terminal());
+        assert!(!Tile::HonorRedDragon.is_terminal());
+        assert!(Tile::HonorRedDragon.is_terminal_or_honor());
+        assert!(!Tile::PinTwo.is_terminal_or_honor());
+    }
+
     fn check_expect_suit(tile: Tile, expected_suit: Option<Suit>, expect_honor: bool) {
         assert_eq!(tile.is_honor(), expect_honor);
         assert_eq!(tile.suit(), expected_suit);
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use crate::mode::Mode;
+use crate::tiles::Tile;
+use crate::{InitialState, Wind};
+
+pub(crate) const HAND_SIZE: usize = 13;
+pub(crate) const DEAD_WALL_SIZE: usize = 14;
+
+// How many of each suit's four fives are red
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct RedFives {
+    #[serde(rename="p")]
+    pub pin: u8,
+    #[serde(rename="s")]
+    pub sou: u8,
+    #[serde(rename="m")]
+    pub man: u8,
+}
+
+impl RedFives {
+    pub const NONE: RedFives = RedFives { pin: 0, sou: 0, man: 0 };
+
+    // Saturates, as the fields may hold counts no full set allows
+    pub fn total(&self) -> u8 {
+        self.pin.saturating_add(self.sou).saturating_add(self.man)
+    }
+
+    // No suit can have more than four, and sanma has no red man
+    pub(crate) fn check(&self, mode: Mode) -> Result<(), SetupError> {
+        if self.pin > 4 || self.sou > 4 || self.man > 4 {
+            return Err(SetupError::InvalidRedFives)
+        }
+        if !mode.has_tile(Tile::ManRedFive) && self.man > 0 {
+            return Err(SetupError::InvalidRedFives)
+        }
+        Ok(())
+    }
+
+    // The expected number of copies of a tile in a full set, with no plain fives left once a suit has
+    // more than four red ones, which check rejects
+    pub fn copies(&self, tile: Tile) -> u8 {
+        match tile {
+            Tile::None => 0,
+            Tile::PinFive => 4u8.saturating_sub(self.pin),
+            Tile::PinRedFive => self.pin,
+            Tile::SouFive => 4u8.saturating_sub(self.sou),
+            Tile::SouRedFive => self.sou,
+            Tile::ManFive => 4u8.saturating_sub(self.man),
+            Tile::ManRedFive => self.man,
+            _ => 4,
+        }
+    }
+}
+
+// One red five per suit
+impl Default for RedFives {
+    fn default() -> Self {
+        RedFives { pin: 1, sou: 1, man: 1 }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SetupError {
+    HandSize { seat: Wind, expected: usize, found: usize },
+    DeadWallSize { found: usize },
+    LivingWallSize { expected: usize, found: usize },
+    NoneTile,
+    TileCount { tile: Tile, expected: u8, found: u8 },
+    // More than four red fives in a suit, or any red man in sanma
+    InvalidRedFives,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::HandSize { seat, expected, found } => write!(f, "{seat:?} starts with {found} tiles instead of {expected}"),
+            SetupError::DeadWallSize { found } => write!(f, "the dead wall holds {found} tiles instead of {DEAD_WALL_SIZE}"),
+            SetupError::LivingWallSize { expected, found } => write!(f, "the living wall holds {found} tiles instead of {expected}"),
+            SetupError::NoneTile => write!(f, "the layout contains Tile::None"),
+            SetupError::TileCount { tile, expected, found } => write!(f, "found {found} of {tile:?} instead of {expected}"),
+            SetupError::InvalidRedFives => write!(f, "a suit cannot have more than four red fives"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+impl InitialState {
+    pub(crate) fn hands(&self) -> [&Vec<Tile>; 4] {
+        [&self.east_hand, &self.south_hand, &self.west_hand, &self.north_hand]
+    }
+
+    // Checks the layout is a legal deal of one full tile set with the given red fives
+    pub fn validate(&self, red_fives: RedFives) -> Result<(), SetupError> {
+        red_fives.check(self.mode)?;
+
+        // Sanma leaves the north hand empty
+        for (seat, hand) in self.hands().into_iter().enumerate() {
+            let seat = Wind::try_from(seat as u8).unwrap();
+            let expected = if self.mode.seats().contains(&seat) { HAND_SIZE } else { 0 };
+            if hand.len() != expected {
+                return Err(SetupError::HandSize { seat, expected, found: hand.len() })
+            }
+        }
+
+        if self.dead_wall.len() != DEAD_WALL_SIZE {
+            return Err(SetupError::DeadWallSize { found: self.dead_wall.len() })
+        }
+
+        let expected = self.mode.tile_count() - self.mode.seats().len() * HAND_SIZE - DEAD_WALL_SIZE;
+        if self.living_wall.len() != expected {
+            return Err(SetupError::LivingWallSize { expected, found: self.living_wall.len() })
+        }
+
+        let mut counts = [0u8; Tile::HonorGreenDragon as usize + 1];
+        let all = self.hands().into_iter().flatten().chain(&self.dead_wall).chain(&self.living_wall);
+        for tile in all {
+            if *tile == Tile::None { return Err(SetupError::NoneTile) }
+            counts[*tile as usize] += 1;
+        }
+
+        for (raw, found) in counts.into_iter().enumerate().skip(1) {
+            let tile = Tile::try_from(raw as u8).unwrap();
+            let expected = if self.mode.has_tile(tile) { red_fives.copies(tile) } else { 0 };
+            if found != expected {
+                return Err(SetupError::TileCount { tile, expected, found })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::validation::*;
+    use crate::test::{empty_init, scripted_init};
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SetupError>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SetupError>();
+    }
+
+    #[test]
+    fn validate_full_set() {
+        let init = scripted_init([&[], &[], &[], &[]], &[]);
+        assert_eq!(init.validate(RedFives::default()), Ok(()));
+        assert_eq!(
+            init.validate(RedFives::NONE),
+            Err(SetupError::TileCount { tile: Tile::PinFive, expected: 4, found: 3 })
+        );
+        assert_eq!(init.validate(RedFives { pin: 5, sou: 0, man: 0 }), Err(SetupError::InvalidRedFives));
+
+        // Counts no set allows stay in range
+        let too_many = RedFives { pin: 5, sou: 200, man: 200 };
+        assert_eq!(too_many.copies(Tile::PinFive), 0);
+        assert_eq!(too_many.copies(Tile::PinRedFive), 5);
+        assert_eq!(too_many.total(), u8::MAX);
+        assert_eq!(empty_init().validate(RedFives::default()), Err(SetupError::HandSize { seat: Wind::East, expected: 13, found: 0 }));
+    }
+
+    #[test]
+    fn validate_sizes() {
+        let mut init = scripted_init([&[], &[], &[], &[]], &[]);
+        let tile = init.west_hand.pop().unwrap();
+        assert_eq!(init.validate(RedFives::default()), Err(SetupError::HandSize { seat: Wind::West, expected: 13, found: 12 }));
+
+        init.west_hand.push(init.dead_wall.pop().unwrap());
+        assert_eq!(init.validate(RedFives::default()), Err(SetupError::DeadWallSize { found: 13 }));
+
+        init.dead_wall.push(tile);
+        init.living_wall.push(Tile::HonorEast);
+        assert_eq!(init.validate(RedFives::default()), Err(SetupError::LivingWallSize { expected: 70, found: 71 }));
+    }
+
+    #[test]
+    fn validate_tiles() {
+        let mut init = scripted_init([&[], &[], &[], &[]], &[]);
+        init.living_wall[0] = Tile::None;
+        assert_eq!(init.validate(RedFives::default()), Err(SetupError::NoneTile));
+
+        // A fifth copy of a tile takes the place of another
+        let mut init = scripted_init([&[Tile::ManOne], &[], &[], &[]], &[Tile::SouNine]);
+        init.living_wall[0] = Tile::ManOne;
+        assert_eq!(
+            init.validate(RedFives::default()),
+            Err(SetupError::TileCount { tile: Tile::SouNine, expected: 4, found: 3 })
+        );
+    }
+}
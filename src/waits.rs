@@ -0,0 +1,108 @@
+use crate::agari::{self, Wait};
+use crate::replay::Meld;
+use crate::tiles::Tile;
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct WaitingTile {
+    // Always the plain tile, as a red five completes the hand the same way
+    pub tile: Tile,
+    // Every shape the tile can complete, in Wait order
+    pub shapes: Vec<Wait>,
+    // The player holds all four copies, so the tile can never arrive
+    pub karaten: bool,
+}
+
+// The tiles that would complete a hand one tile short of a win, with melds counting for three tiles each.
+// Hands of any other size wait on nothing.
+pub fn waits(concealed: &[Tile], melds: &[Meld]) -> Vec<WaitingTile> {
+    if concealed.len() + 3 * melds.len() != 13 { return vec![] }
+
+    let all: Vec<Tile> = concealed.iter().chain(melds.iter().flat_map(|meld| meld.tiles.iter())).copied().collect();
+    let held = agari::to_counts(&all);
+    let mut tiles = concealed.to_vec();
+    let mut found = vec![];
+    for (kind, &count) in held.iter().enumerate() {
+        let tile = Tile::from_kind(kind);
+        tiles.push(tile);
+        let mut shapes: Vec<Wait> = agari::decompose(&tiles, melds).decompositions.iter()
+            .flat_map(|decomposition| agari::placements(decomposition, tile, melds.len()))
+            .map(|(wait, _)| wait)
+            .collect();
+        tiles.pop();
+
+        if shapes.is_empty() { continue }
+        shapes.sort_unstable_by_key(|wait| *wait as u8);
+        shapes.dedup();
+        found.push(WaitingTile { tile, shapes, karaten: count >= 4 });
+    }
+    found
+}
+
+// Tenpai counts karaten waits, as the hand is still shaped to win
+pub fn is_tenpai(concealed: &[Tile], melds: &[Meld]) -> bool {
+    !waits(concealed, melds).is_empty()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::waits::*;
+    use crate::replay::MeldKind;
+    use crate::tiles::Tile::*;
+    use crate::Wind;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<WaitingTile>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<WaitingTile>();
+    }
+
+    fn summary(concealed: &[Tile], melds: &[Meld]) -> Vec<(Tile, Vec<Wait>, bool)> {
+        waits(concealed, melds).into_iter().map(|wait| (wait.tile, wait.shapes, wait.karaten)).collect()
+    }
+
+    #[test]
+    fn finds_shapes() {
+        let ryanmen = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, HonorEast, HonorEast, ManThree, ManFour];
+        assert_eq!(summary(&ryanmen, &[]), vec![(ManTwo, vec![Wait::Ryanmen], false), (ManFive, vec![Wait::Ryanmen], false)]);
+
+        // 1112 waits on 2 as a pair, or on 3 to finish 1-2
+        let mixed = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, ManOne, ManOne, ManOne, ManTwo];
+        assert_eq!(summary(&mixed, &[]), vec![(ManTwo, vec![Wait::Tanki], false), (ManThree, vec![Wait::Penchan], false)]);
+
+        let shanpon = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, HonorEast, HonorEast, SouNine, SouNine];
+        assert_eq!(summary(&shanpon, &[]), vec![(SouNine, vec![Wait::Shanpon], false), (HonorEast, vec![Wait::Shanpon], false)]);
+
+        let kanchan = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, HonorEast, HonorEast, PinFive, PinSeven];
+        assert_eq!(summary(&kanchan, &[]), vec![(PinSix, vec![Wait::Kanchan], false)]);
+
+        assert!(!is_tenpai(&kanchan[1..], &[]));
+        assert!(waits(&ryanmen[..12], &[]).is_empty());
+    }
+
+    #[test]
+    fn flags_karaten() {
+        let pon = Meld { kind: MeldKind::Pon, tiles: vec![SouFour; 3], called: Some(SouFour), from: Some(Wind::North) };
+        let concealed = [PinOne, PinTwo, PinThree, ManSeven, ManEight, ManNine, HonorEast, HonorEast, SouFour, SouFive];
+        assert_eq!(summary(&concealed, &[pon]), vec![(SouThree, vec![Wait::Ryanmen], false), (SouSix, vec![Wait::Ryanmen], false)]);
+
+        let pairs = [PinOne, PinOne, SouTwo, SouTwo, ManThree, ManThree, HonorEast, HonorEast, HonorNorth, HonorNorth, ManNine, ManNine, ManNine];
+        assert_eq!(summary(&pairs[..12].iter().copied().chain([PinOne]).collect::<Vec<_>>(), &[]), vec![]);
+
+        // Waiting on a fifth copy, or on a tile whose other copies sit in a meld
+        let quad = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, HonorWest, HonorWest, HonorWest, HonorWest];
+        assert_eq!(waits(&quad, &[]).iter().map(|wait| (wait.tile, wait.karaten)).collect::<Vec<_>>(), vec![(HonorWest, true)]);
+        let chii = Meld { kind: MeldKind::Chii, tiles: vec![ManTwo, ManThree, ManFour], called: Some(ManTwo), from: Some(Wind::North) };
+        let concealed = [PinOne, PinTwo, PinThree, ManTwo, ManTwo, ManTwo, HonorEast, HonorEast, ManOne, ManThree];
+        let melds = [chii];
+        // The 2s also read as a shanpon with the easts
+        assert_eq!(summary(&concealed, &melds), vec![(ManTwo, vec![Wait::Kanchan, Wait::Shanpon], true), (HonorEast, vec![Wait::Shanpon], false)]);
+        assert!(is_tenpai(&concealed, &melds));
+        assert_eq!(summary(&pairs, &[]), vec![]);
+    }
+}
@@ -0,0 +1,497 @@
+use crate::agari::{self, Decomposition, SetKind, Wait, ORPHANS};
+use crate::mode::Mode;
+use crate::replay::{Meld, MeldKind, RuleError};
+use crate::ruleset::Ruleset;
+use crate::scoring;
+use crate::tiles::Tile;
+use crate::Wind;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Yaku {
+    Riichi,
+    DoubleRiichi,
+    // Counted on top of either riichi
+    OpenRiichi,
+    Ippatsu,
+    MenzenTsumo,
+    Pinfu,
+    Tanyao,
+    Iipeikou,
+    SeatWind,
+    PrevailingWind,
+    Haku,
+    Hatsu,
+    Chun,
+    Haitei,
+    Houtei,
+    Rinshan,
+    Chankan,
+    Sanshoku,
+    Ittsu,
+    Chanta,
+    Chiitoitsu,
+    Toitoi,
+    Sanankou,
+    SanshokuDoukou,
+    Sankantsu,
+    Shousangen,
+    Honroutou,
+    Ryanpeikou,
+    Junchan,
+    Honitsu,
+    Chinitsu,
+    // Yakuman, where the named waits replace their plain forms
+    Tenhou,
+    Chiihou,
+    Kokushi,
+    KokushiJuusanmen,
+    Suuankou,
+    SuuankouTanki,
+    Daisangen,
+    Shousuushii,
+    Daisuushii,
+    Tsuuiisou,
+    Chinroutou,
+    Ryuuiisou,
+    Chuuren,
+    JunseiChuuren,
+    Suukantsu,
+}
+
+impl Yaku {
+    pub fn is_yakuman(&self) -> bool {
+        *self >= Yaku::Tenhou
+    }
+
+    // Yakuman count as 13 han each. Yaku that are only allowed closed are never awarded open.
+    pub fn han(&self, closed: bool) -> u8 {
+        let reduced = |han: u8| if closed { han } else { han - 1 };
+        match self {
+            Yaku::DoubleRiichi | Yaku::Chiitoitsu | Yaku::Toitoi | Yaku::Sanankou |
+            Yaku::SanshokuDoukou | Yaku::Sankantsu | Yaku::Shousangen | Yaku::Honroutou => 2,
+            Yaku::Sanshoku | Yaku::Ittsu | Yaku::Chanta => reduced(2),
+            Yaku::Ryanpeikou => 3,
+            Yaku::Junchan | Yaku::Honitsu => reduced(3),
+            Yaku::Chinitsu => reduced(6),
+            yaku if yaku.is_yakuman() => 13,
+            _ => 1,
+        }
+    }
+}
+
+// Everything about a win that the tiles alone don't tell
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub(crate) struct WinContext {
+    pub seat: Wind,
+    pub prevailing_wind: Wind,
+    pub tile: Tile,
+    // The discarder, or None for tsumo
+    pub from: Option<Wind>,
+    pub riichi: bool,
+    pub double_riichi: bool,
+    pub open_riichi: bool,
+    pub ippatsu: bool,
+    // Won on the last tile of the living wall
+    pub last_tile: bool,
+    pub rinshan: bool,
+    pub chankan: bool,
+    // Won on the seat's first draw before any call or kan
+    pub first_draw: bool,
+    pub dora: u8,
+    pub red_fives: u8,
+    // Zero unless the winner is in riichi
+    pub ura_dora: u8,
+    // North tiles set aside in sanma, a dora each
+    pub kita: u8,
+    pub mode: Mode,
+    pub ruleset: Ruleset,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Win {
+    pub seat: Wind,
+    // The discarder, or None for tsumo
+    pub from: Option<Wind>,
+    pub tile: Tile,
+    pub closed: bool,
+    // Only the yakuman when there are any
+    pub yaku: Vec<Yaku>,
+    // The reading of the hand the yaku were counted on
+    pub decomposition: Decomposition,
+    pub wait: Wait,
+    pub fu: u8,
+    pub dora: u8,
+    pub red_fives: u8,
+    pub ura_dora: u8,
+    pub kita: u8,
+    // Sanma tsumo wins go unpaid by the empty north seat
+    pub mode: Mode,
+    // Decides whether the score is rounded up to mangan
+    pub ruleset: Ruleset,
+    // Called as ron while in furiten, which the log records but the rules forbid
+    pub furiten: bool,
+}
+
+impl Win {
+    // Han from yaku alone, see score for the total with dora
+    pub fn han(&self) -> u8 {
+        self.yaku.iter().map(|yaku| yaku.han(self.closed)).sum()
+    }
+
+    pub fn is_yakuman(&self) -> bool {
+        self.yaku.iter().any(Yaku::is_yakuman)
+    }
+}
+
+const CHUN: usize = 31;
+const HAKU: usize = 32;
+const HATSU: usize = 33;
+const GREEN: [usize; 6] = [10, 11, 12, 14, 16, HATSU];
+
+// Reads the concealed tiles, winning tile included, every possible way and keeps the reading worth the most points
+pub(crate) fn evaluate(concealed: &[Tile], melds: &[Meld], context: &WinContext) -> Result<Win, RuleError> {
+    let agari = agari::decompose(concealed, melds);
+    if !agari.is_complete() { return Err(RuleError::IncompleteHand) }
+
+    let closed = melds.iter().all(|meld| meld.kind == MeldKind::ClosedKan);
+    let mut best: Option<Win> = None;
+    for decomposition in agari.decompositions {
+        for (wait, finished) in agari::placements(&decomposition, context.tile, melds.len()) {
+            let mut yaku = count_yaku(concealed, melds, context, closed, &decomposition, wait, finished);
+            if yaku.is_empty() { continue }
+            yaku.sort_unstable();
+
+            let fu = scoring::fu(&decomposition, wait, finished, context, closed, &yaku);
+            let win = Win {
+                seat: context.seat,
+                from: context.from,
+                tile: context.tile,
+                closed,
+                yaku,
+                decomposition: decomposition.clone(),
+                wait,
+                fu,
+                dora: context.dora,
+                red_fives: context.red_fives,
+                ura_dora: context.ura_dora,
+                kita: context.kita,
+                mode: context.mode,
+                ruleset: context.ruleset,
+                furiten: false,
+            };
+            let key = |win: &Win| {
+                let score = win.score();
+                (score.basic_points, score.han, score.fu)
+            };
+            if best.as_ref().is_none_or(|best| key(&win) > key(best)) {
+                best = Some(win);
+            }
+        }
+    }
+
+    best.ok_or(RuleError::NoYaku)
+}
+
+fn count_yaku(
+    concealed: &[Tile],
+    melds: &[Meld],
+    context: &WinContext,
+    closed: bool,
+    decomposition: &Decomposition,
+    wait: Wait,
+    finished: Option<usize>,
+) -> Vec<Yaku> {
+    let tsumo = context.from.is_none();
+    let mut yakuman = vec![];
+    let mut yaku = vec![];
+
+    if context.first_draw && tsumo {
+        yakuman.push(if context.seat == Wind::East { Yaku::Tenhou } else { Yaku::Chiihou });
+    }
+
+    // Each group of the hand as the kinds it holds, with quads cut down to three
+    let groups: Vec<Vec<usize>> = match decomposition {
+        Decomposition::Standard { pair, sets } => {
+            let pair = pair.kind().unwrap();
+            std::iter::once(vec![pair, pair])
+                .chain(sets.iter().map(|set| {
+                    let kind = set.tile.kind().unwrap();
+                    match set.kind {
+                        SetKind::Sequence => vec![kind, kind + 1, kind + 2],
+                        SetKind::Triplet | SetKind::Quad => vec![kind; 3],
+                    }
+                }))
+                .collect()
+        },
+        Decomposition::Chiitoitsu { pairs } => pairs.iter().map(|pair| vec![pair.kind().unwrap(); 2]).collect(),
+        Decomposition::Kokushi { .. } => {
+            let mut counts = agari::to_counts(concealed);
+            counts[context.tile.kind().unwrap()] -= 1;
+            let juusanmen = ORPHANS.iter().all(|&kind| counts[kind] > 0);
+            yakuman.push(if juusanmen { Yaku::KokushiJuusanmen } else { Yaku::Kokushi });
+            return yakuman
+        },
+    };
+
+    let kinds = || groups.iter().flatten().copied();
+    let is_honor = |kind: usize| kind >= 27;
+    let is_terminal_or_honor = |kind: usize| Tile::from_kind(kind).is_terminal_or_honor();
+    let has_honors = kinds().any(is_honor);
+    let suits = (0..3).filter(|&suit| kinds().any(|kind| kind / 9 == suit)).count();
+
+    if suits == 0 { yakuman.push(Yaku::Tsuuiisou) }
+    if !has_honors && kinds().all(is_terminal_or_honor) { yakuman.push(Yaku::Chinroutou) }
+    if kinds().all(|kind| GREEN.contains(&kind)) { yakuman.push(Yaku::Ryuuiisou) }
+
+    // Nine gates has to be shown with every tile concealed
+    if melds.is_empty() && suits == 1 && !has_honors {
+        let counts = agari::to_counts(concealed);
+        let suit = kinds().next().unwrap() / 9;
+        let numbers = &counts[suit * 9..suit * 9 + 9];
+        let base = [3, 1, 1, 1, 1, 1, 1, 1, 3];
+        if numbers.iter().zip(base).all(|(&count, needed)| count >= needed) {
+            let extra = numbers.iter().zip(base).position(|(&count, needed)| count > needed).unwrap();
+            let junsei = suit * 9 + extra == context.tile.kind().unwrap();
+            yakuman.push(if junsei { Yaku::JunseiChuuren } else { Yaku::Chuuren });
+        }
+    }
+
+    let seat_wind = context.seat.tile().kind().unwrap();
+    let prevailing_wind = context.prevailing_wind.tile().kind().unwrap();
+
+    if let Decomposition::Standard { pair, sets } = decomposition {
+        let pair = pair.kind().unwrap();
+        let mut sequences = vec![];
+        let mut triplets = vec![];
+        let mut concealed_triplets = 0;
+        let mut quads = 0;
+        for (index, set) in sets.iter().enumerate() {
+            let kind = set.tile.kind().unwrap();
+            if set.kind == SetKind::Sequence {
+                sequences.push(kind);
+                continue
+            }
+            triplets.push(kind);
+            quads += (set.kind == SetKind::Quad) as usize;
+            // A triplet finished by ron counts as open
+            let by_ron = !tsumo && finished == Some(index);
+            concealed_triplets += (!set.open && !by_ron) as usize;
+        }
+        sequences.sort_unstable();
+
+        let dragons = triplets.iter().filter(|&&kind| kind >= CHUN).count();
+        let winds = triplets.iter().filter(|&&kind| (27..CHUN).contains(&kind)).count();
+        if dragons == 3 { yakuman.push(Yaku::Daisangen) }
+        if winds == 4 { yakuman.push(Yaku::Daisuushii) }
+        if winds == 3 && (27..CHUN).contains(&pair) { yakuman.push(Yaku::Shousuushii) }
+        if concealed_triplets == 4 {
+            yakuman.push(if wait == Wait::Tanki { Yaku::SuuankouTanki } else { Yaku::Suuankou });
+        }
+        if quads == 4 { yakuman.push(Yaku::Suukantsu) }
+
+        let is_value = |kind: usize| kind >= CHUN || kind == seat_wind || kind == prevailing_wind;
+        if closed && sequences.len() == 4 && wait == Wait::Ryanmen && !is_value(pair) {
+            yaku.push(Yaku::Pinfu);
+        }
+
+        let mut repeats = 0;
+        let mut index = 0;
+        while index + 1 < sequences.len() {
+            if sequences[index] == sequences[index + 1] {
+                repeats += 1;
+                index += 2;
+            }
+            else {
+                index += 1;
+            }
+        }
+        if closed && repeats == 1 { yaku.push(Yaku::Iipeikou) }
+        if closed && repeats == 2 { yaku.push(Yaku::Ryanpeikou) }
+
+        if triplets.contains(&seat_wind) { yaku.push(Yaku::SeatWind) }
+        if triplets.contains(&prevailing_wind) { yaku.push(Yaku::PrevailingWind) }
+        if triplets.contains(&HAKU) { yaku.push(Yaku::Haku) }
+        if triplets.contains(&HATSU) { yaku.push(Yaku::Hatsu) }
+        if triplets.contains(&CHUN) { yaku.push(Yaku::Chun) }
+
+        if (0..7).any(|start| [start, start + 9, start + 18].iter().all(|kind| sequences.contains(kind))) {
+            yaku.push(Yaku::Sanshoku);
+        }
+        if (0..3).any(|suit| [0, 3, 6].iter().all(|offset| sequences.contains(&(suit * 9 + offset)))) {
+            yaku.push(Yaku::Ittsu);
+        }
+        if triplets.len() == 4 { yaku.push(Yaku::Toitoi) }
+        if concealed_triplets == 3 { yaku.push(Yaku::Sanankou) }
+        if (0..9).any(|number| [number, number + 9, number + 18].iter().all(|kind| triplets.contains(kind))) {
+            yaku.push(Yaku::SanshokuDoukou);
+        }
+        if quads == 3 { yaku.push(Yaku::Sankantsu) }
+        if dragons == 2 && pair >= CHUN { yaku.push(Yaku::Shousangen) }
+
+        // Every group touches a terminal or honor, and at least one is a sequence
+        let outside = groups.iter().all(|group| group.iter().copied().any(is_terminal_or_honor));
+        if outside && !sequences.is_empty() {
+            yaku.push(if has_honors { Yaku::Chanta } else { Yaku::Junchan });
+        }
+    }
+    else {
+        yaku.push(Yaku::Chiitoitsu);
+    }
+
+    if !yakuman.is_empty() { return yakuman }
+
+    if context.double_riichi { yaku.push(Yaku::DoubleRiichi) }
+    else if context.riichi { yaku.push(Yaku::Riichi) }
+    if context.open_riichi { yaku.push(Yaku::OpenRiichi) }
+    if context.ippatsu { yaku.push(Yaku::Ippatsu) }
+    if closed && tsumo { yaku.push(Yaku::MenzenTsumo) }
+    if context.last_tile && tsumo && !context.rinshan { yaku.push(Yaku::Haitei) }
+    if context.last_tile && !tsumo { yaku.push(Yaku::Houtei) }
+    if context.rinshan { yaku.push(Yaku::Rinshan) }
+    if context.chankan { yaku.push(Yaku::Chankan) }
+
+    let kuitan = closed || context.ruleset.kuitan;
+    if kuitan && kinds().all(|kind| !is_terminal_or_honor(kind)) { yaku.push(Yaku::Tanyao) }
+    if has_honors && suits > 0 && kinds().all(is_terminal_or_honor) { yaku.push(Yaku::Honroutou) }
+    if suits == 1 {
+        yaku.push(if has_honors { Yaku::Honitsu } else { Yaku::Chinitsu });
+    }
+
+    yaku
+}
+
+#[cfg(test)]
+mod test {
+    use crate::yaku::*;
+    use crate::tiles::Tile::*;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Win>();
+    }
+
+    #[test]
+    fn test_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Win>();
+    }
+
+    fn context(tile: Tile, from: Option<Wind>) -> WinContext {
+        WinContext {
+            seat: Wind::South,
+            prevailing_wind: Wind::East,
+            tile,
+            from,
+            riichi: false,
+            double_riichi: false,
+            open_riichi: false,
+            ippatsu: false,
+            last_tile: false,
+            rinshan: false,
+            chankan: false,
+            first_draw: false,
+            dora: 0,
+            red_fives: 0,
+            ura_dora: 0,
+            kita: 0,
+            mode: Mode::Yonma,
+            ruleset: Ruleset::default(),
+        }
+    }
+
+    fn pon(tile: Tile) -> Meld {
+        Meld { kind: MeldKind::Pon, tiles: vec![tile; 3], called: Some(tile), from: Some(Wind::East) }
+    }
+
+    fn yaku_of(concealed: &[Tile], melds: &[Meld], context: &WinContext) -> Vec<Yaku> {
+        evaluate(concealed, melds, context).unwrap().yaku
+    }
+
+    #[test]
+    fn closed_hands() {
+        let pinfu = [PinOne, PinTwo, PinThree, PinFour, PinFive, PinSix, SouTwo, SouThree, SouFour, ManSix, ManSeven, ManEight, ManNine, ManNine];
+        let win = evaluate(&pinfu, &[], &context(ManSix, Some(Wind::West))).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::Pinfu]);
+        assert_eq!(win.wait, Wait::Ryanmen);
+        assert_eq!(win.han(), 1);
+
+        // Winning on the 3 of 1-2 is no longer pinfu, but tsumo still counts
+        let mut tsumo = context(PinThree, Option::None);
+        tsumo.riichi = true;
+        tsumo.ippatsu = true;
+        assert_eq!(yaku_of(&pinfu, &[], &tsumo), vec![Yaku::Riichi, Yaku::Ippatsu, Yaku::MenzenTsumo]);
+
+        let tanyao = [PinTwo, PinThree, PinFour, PinTwo, PinThree, PinFour, SouFive, SouSix, SouSeven, ManSix, ManSeven, ManEight, ManFive, ManRedFive];
+        assert_eq!(yaku_of(&tanyao, &[], &context(SouSeven, Some(Wind::West))), vec![Yaku::Pinfu, Yaku::Tanyao, Yaku::Iipeikou]);
+
+        let pairs = [PinOne, PinOne, PinNine, PinNine, SouTwo, SouTwo, SouThree, SouThree, HonorEast, HonorEast, HonorNorth, HonorNorth, HonorRedDragon, HonorRedDragon];
+        assert_eq!(yaku_of(&pairs, &[], &context(PinOne, Some(Wind::West))), vec![Yaku::Chiitoitsu]);
+    }
+
+    #[test]
+    fn best_reading() {
+        // Read as three identical sequences, or as three concealed triplets
+        let hand = [PinOne, PinOne, PinOne, PinTwo, PinTwo, PinTwo, PinThree, PinThree, PinThree, SouSeven, SouEight, SouNine, ManFive, ManFive];
+        let win = evaluate(&hand, &[], &context(ManFive, Some(Wind::West))).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::Sanankou]);
+        assert_eq!(win.wait, Wait::Tanki);
+        let win = evaluate(&hand, &[], &context(ManFive, Option::None)).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::MenzenTsumo, Yaku::Sanankou]);
+
+        // A triplet completed by ron is open, so this is only toitoi with two concealed triplets
+        let shanpon = [PinOne, PinOne, PinOne, SouNine, SouNine, SouNine, ManTwo, ManTwo, ManTwo, HonorWest, HonorWest];
+        let melds = [pon(HonorSouth)];
+        let win = evaluate(&shanpon, &melds, &context(ManTwo, Some(Wind::West))).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::SeatWind, Yaku::Toitoi]);
+        assert!(!win.closed);
+        assert_eq!(win.han(), 3);
+    }
+
+    #[test]
+    fn open_hands() {
+        let honitsu = [ManOne, ManTwo, ManThree, ManSeven, ManEight, ManNine, ManNine, ManNine, HonorRedDragon, HonorRedDragon, HonorRedDragon];
+        let win = evaluate(&honitsu, &[pon(HonorEast)], &context(ManNine, Some(Wind::North))).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::PrevailingWind, Yaku::Chun, Yaku::Chanta, Yaku::Honitsu]);
+        assert_eq!(win.han(), 5);
+
+        let no_yaku = [PinOne, PinTwo, PinThree, SouFour, SouFive, SouSix, ManSeven, ManEight, ManNine, ManOne, ManOne];
+        assert_eq!(evaluate(&no_yaku, &[pon(SouTwo)], &context(PinThree, Some(Wind::North))), Err(RuleError::NoYaku));
+        assert_eq!(evaluate(&no_yaku[1..], &[pon(SouTwo)], &context(PinThree, Some(Wind::North))), Err(RuleError::IncompleteHand));
+
+        let mut houtei = context(PinThree, Some(Wind::North));
+        houtei.last_tile = true;
+        assert_eq!(yaku_of(&no_yaku, &[pon(SouTwo)], &houtei), vec![Yaku::Houtei]);
+
+        // Kuitan only matters once the hand is open
+        let tanyao = [PinTwo, PinThree, PinFour, SouFour, SouFive, SouSix, ManSix, ManSeven, ManEight, ManTwo, ManTwo];
+        let mut no_kuitan = context(PinFour, Some(Wind::North));
+        no_kuitan.ruleset.kuitan = false;
+        assert_eq!(yaku_of(&tanyao, &[pon(SouTwo)], &context(PinFour, Some(Wind::North))), vec![Yaku::Tanyao]);
+        assert_eq!(evaluate(&tanyao, &[pon(SouTwo)], &no_kuitan), Err(RuleError::NoYaku));
+        let closed = [PinTwo, PinThree, PinFour, SouFour, SouFive, SouSix, ManSix, ManSeven, ManEight, ManTwo, ManTwo, SouTwo, SouTwo, SouTwo];
+        assert_eq!(yaku_of(&closed, &[], &no_kuitan), vec![Yaku::Tanyao]);
+    }
+
+    #[test]
+    fn yakuman() {
+        let orphans = [PinOne, PinNine, SouOne, SouNine, ManOne, ManNine, HonorEast, HonorSouth, HonorWest, HonorNorth, HonorRedDragon, HonorWhiteDragon, HonorGreenDragon, HonorGreenDragon];
+        assert_eq!(yaku_of(&orphans, &[], &context(HonorRedDragon, Some(Wind::East))), vec![Yaku::Kokushi]);
+        assert_eq!(yaku_of(&orphans, &[], &context(HonorGreenDragon, Some(Wind::East))), vec![Yaku::KokushiJuusanmen]);
+
+        let gates = [ManOne, ManOne, ManOne, ManTwo, ManThree, ManFour, ManFive, ManSix, ManSeven, ManEight, ManNine, ManNine, ManNine, ManFive];
+        assert_eq!(yaku_of(&gates, &[], &context(ManFive, Some(Wind::East))), vec![Yaku::JunseiChuuren]);
+        assert_eq!(yaku_of(&gates, &[], &context(ManTwo, Some(Wind::East))), vec![Yaku::Chuuren]);
+
+        let dragons = [HonorRedDragon, HonorRedDragon, HonorRedDragon, HonorWhiteDragon, HonorWhiteDragon, HonorWhiteDragon, SouTwo, SouThree, SouFour, SouEight, SouEight];
+        let win = evaluate(&dragons, &[pon(HonorGreenDragon)], &context(SouEight, Some(Wind::East))).unwrap();
+        assert_eq!(win.yaku, vec![Yaku::Daisangen]);
+        assert_eq!(win.han(), 13);
+
+        let green = [SouTwo, SouTwo, SouTwo, SouThree, SouThree, SouThree, SouFour, SouFour, SouFour, SouSix, SouSix, SouEight, SouEight, SouEight];
+        let mut tenhou = context(SouSix, Option::None);
+        tenhou.seat = Wind::East;
+        tenhou.first_draw = true;
+        assert_eq!(yaku_of(&green, &[], &tenhou), vec![Yaku::Tenhou, Yaku::SuuankouTanki, Yaku::Ryuuiisou]);
+    }
+}